@@ -0,0 +1,16 @@
+use pinocchio::error::ProgramError;
+
+#[repr(u32)]
+pub enum EscrowError {
+    Expired = 0,
+    NotExpired = 1,
+    InvalidTaker = 2,
+    NativeNotSupported = 3,
+    OfferChanged = 4,
+}
+
+impl From<EscrowError> for ProgramError {
+    fn from(error: EscrowError) -> Self {
+        ProgramError::Custom(error as u32)
+    }
+}
@@ -0,0 +1,175 @@
+use pinocchio::{
+    cpi::{Seed, Signer},
+    error::ProgramError,
+    sysvars::{clock::Clock, Sysvar},
+    AccountView, ProgramResult,
+};
+use pinocchio_pubkey::derive_address;
+use pinocchio_system::instructions::Transfer;
+
+use crate::{
+    errors::EscrowError,
+    native::{is_native, transfer_lamports},
+    state::Escrow,
+    token::{check_token_account, check_token_program, mint_decimals, TransferChecked},
+};
+
+use wincode::SchemaRead;
+
+// The offer as it should stand after the amend. A higher `amount_to_give`
+// tops the deposit up from the maker's ATA, a lower one withdraws the
+// difference back to it.
+#[derive(SchemaRead)]
+pub struct AmendInstructionData {
+    pub amount_to_receive: u64,
+    pub amount_to_give: u64,
+}
+
+impl AmendInstructionData {
+    pub const LEN: usize = 8 + 8;
+}
+
+// Reprices an open offer in place, keeping its PDA, seed and rent. Emptying
+// the deposit is what `Cancel` is for, so both amounts must stay non-zero.
+// An expired offer can only be cancelled or reclaimed, not revived.
+pub fn process_amend_instruction(accounts: &[AccountView], data: &[u8]) -> ProgramResult {
+    let [
+        maker,
+        mint_a,
+        maker_ata_a,
+        escrow_account,
+        escrow_ata,
+        system_program,
+        token_program,
+        remaining_accounts @ ..
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !maker.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    check_token_program(token_program)?;
+    if system_program.address() != &pinocchio_system::ID {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    if data.len() != AmendInstructionData::LEN {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let ix_data: AmendInstructionData =
+        wincode::deserialize(data).map_err(|_| ProgramError::InvalidInstructionData)?;
+    if ix_data.amount_to_receive == 0 || ix_data.amount_to_give == 0 {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let mint_a_decimals = if is_native(mint_a) {
+        None
+    } else {
+        let decimals = mint_decimals(mint_a)?;
+        unsafe {
+            if mint_a.owner() != token_program.address() {
+                return Err(ProgramError::IllegalOwner);
+            }
+        }
+
+        check_token_account(maker_ata_a, mint_a, maker.address())?;
+        Some(decimals)
+    };
+
+    let (amount_to_give, bump_bytes, escrow_seed_bytes) = {
+        let escrow_state = Escrow::from_account_info(escrow_account)?;
+        let seeds = [
+            b"escrow",
+            maker.address().as_ref(),
+            &escrow_state.seed().to_le_bytes(),
+            &[escrow_state.bump],
+        ];
+        let escrow_account_pda = derive_address(&seeds, None, &crate::ID.as_array());
+
+        if escrow_state.maker() != *maker.address()
+            || escrow_state.mint_a() != *mint_a.address()
+            || escrow_account_pda != *escrow_account.address().as_array()
+        {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if escrow_state.is_expired(Clock::get()?.unix_timestamp) {
+            return Err(EscrowError::Expired.into());
+        }
+
+        if mint_a_decimals.is_some() {
+            check_token_account(escrow_ata, mint_a, escrow_account.address())?;
+        }
+
+        escrow_state.set_amount_to_receive(ix_data.amount_to_receive);
+
+        let amount_to_give = escrow_state.amount_to_give();
+        let bump_bytes = [escrow_state.bump];
+        let escrow_seed_bytes = escrow_state.seed().to_le_bytes();
+
+        (amount_to_give, bump_bytes, escrow_seed_bytes)
+    };
+
+    if ix_data.amount_to_give > amount_to_give {
+        let top_up = ix_data.amount_to_give - amount_to_give;
+
+        match mint_a_decimals {
+            Some(mint_a_decimals) => TransferChecked {
+                from: maker_ata_a,
+                mint: mint_a,
+                to: escrow_ata,
+                authority: maker,
+                amount: top_up,
+                decimals: mint_a_decimals,
+                token_program,
+                extra_accounts: remaining_accounts,
+            }
+            .invoke()?,
+            None => Transfer {
+                from: maker,
+                to: escrow_account,
+                lamports: top_up,
+            }
+            .invoke()?,
+        }
+    } else if ix_data.amount_to_give < amount_to_give {
+        let withdrawal = amount_to_give - ix_data.amount_to_give;
+
+        match mint_a_decimals {
+            Some(mint_a_decimals) => {
+                let vault_seed = [
+                    Seed::from(b"escrow"),
+                    Seed::from(maker.address().as_array()),
+                    Seed::from(&escrow_seed_bytes),
+                    Seed::from(&bump_bytes),
+                ];
+                let signer = Signer::from(&vault_seed);
+
+                TransferChecked {
+                    from: escrow_ata,
+                    mint: mint_a,
+                    to: maker_ata_a,
+                    authority: escrow_account,
+                    amount: withdrawal,
+                    decimals: mint_a_decimals,
+                    token_program,
+                    extra_accounts: remaining_accounts,
+                }
+                .invoke_signed(&[signer])?;
+            }
+            None => transfer_lamports(escrow_account, maker, withdrawal)?,
+        }
+    }
+
+    // As in `Make`, a transfer fee on a top-up means less reaches the vault
+    // than was sent, so the offer follows the vault rather than the request.
+    let deposited = match mint_a_decimals {
+        Some(_) => check_token_account(escrow_ata, mint_a, escrow_account.address())?,
+        None => ix_data.amount_to_give,
+    };
+    Escrow::from_account_info(escrow_account)?.set_amount_to_give(deposited);
+
+    Ok(())
+}
@@ -4,9 +4,12 @@ use pinocchio::{
     AccountView, ProgramResult,
 };
 use pinocchio_pubkey::derive_address;
-use pinocchio_token::state::{Mint, TokenAccount};
 
-use crate::state::Escrow;
+use crate::{
+    native::is_native,
+    state::Escrow,
+    token::{check_token_account, check_token_program, mint_decimals, CloseAccount, TransferChecked},
+};
 
 pub fn process_cancel_instruction(accounts: &[AccountView], _data: &[u8]) -> ProgramResult {
     let [
@@ -17,7 +20,7 @@ pub fn process_cancel_instruction(accounts: &[AccountView], _data: &[u8]) -> Pro
         escrow_ata, 
         system_program, 
         token_program, 
-        _remaining_accounts @ ..
+        remaining_accounts @ ..
     ] = accounts else {
         return Err(ProgramError::NotEnoughAccountKeys);
     };
@@ -26,29 +29,24 @@ pub fn process_cancel_instruction(accounts: &[AccountView], _data: &[u8]) -> Pro
         return Err(ProgramError::MissingRequiredSignature);
     }
     
-    if token_program.address() != &pinocchio_token::ID {
-        return Err(ProgramError::IncorrectProgramId);
-    }
+    check_token_program(token_program)?;
     if system_program.address() != &pinocchio_system::ID {
         return Err(ProgramError::IncorrectProgramId);
     }
 
-    let _mint_a_state = Mint::from_account_view(mint_a)?;
-    unsafe {
-        if mint_a.owner() != token_program.address() {
-            return Err(ProgramError::IllegalOwner);
+    let mint_a_decimals = if is_native(mint_a) {
+        None
+    } else {
+        let decimals = mint_decimals(mint_a)?;
+        unsafe {
+            if mint_a.owner() != token_program.address() {
+                return Err(ProgramError::IllegalOwner);
+            }
         }
-    }
 
-    {
-        let maker_ata_a_state = TokenAccount::from_account_view(maker_ata_a)?;
-        if maker_ata_a_state.mint() != mint_a.address() {
-            return Err(ProgramError::InvalidAccountData);
-        }
-        if maker_ata_a_state.owner() != maker.address() {
-            return Err(ProgramError::IllegalOwner);
-        }
-    }
+        check_token_account(maker_ata_a, mint_a, maker.address())?;
+        Some(decimals)
+    };
 
     let (amount_to_give, bump_bytes, escrow_seed_bytes) = {
         let escrow_state = Escrow::from_account_info(escrow_account)?;
@@ -67,9 +65,8 @@ pub fn process_cancel_instruction(accounts: &[AccountView], _data: &[u8]) -> Pro
             return Err(ProgramError::InvalidAccountData);
         }
 
-        let escrow_ata_state = TokenAccount::from_account_view(&escrow_ata)?;
-        if escrow_ata_state.owner() != escrow_account.address() {
-            return Err(ProgramError::IllegalOwner);
+        if mint_a_decimals.is_some() {
+            check_token_account(escrow_ata, mint_a, escrow_account.address())?;
         }
 
         let amount_to_give = escrow_state.amount_to_give();
@@ -79,28 +76,38 @@ pub fn process_cancel_instruction(accounts: &[AccountView], _data: &[u8]) -> Pro
         (amount_to_give, bump_bytes, escrow_seed_bytes)
     };
 
-    let vault_seed = [
-        Seed::from(b"escrow"),
-        Seed::from(maker.address().as_array()),
-        Seed::from(&escrow_seed_bytes),
-        Seed::from(&bump_bytes),
-    ];
-    let signer = Signer::from(&vault_seed);
-
-    pinocchio_token::instructions::Transfer {
-        from: escrow_ata,
-        to: maker_ata_a,
-        authority: escrow_account,
-        amount: amount_to_give,
-    }
-    .invoke_signed(&[signer.clone()])?;
-
-    pinocchio_token::instructions::CloseAccount {
-        account: escrow_ata,
-        destination: maker,
-        authority: escrow_account,
+    // A native SOL deposit is part of the escrow's own balance and goes back
+    // to the maker with its rent when the escrow is closed below.
+    if let Some(mint_a_decimals) = mint_a_decimals {
+        let vault_seed = [
+            Seed::from(b"escrow"),
+            Seed::from(maker.address().as_array()),
+            Seed::from(&escrow_seed_bytes),
+            Seed::from(&bump_bytes),
+        ];
+        let signer = Signer::from(&vault_seed);
+
+        TransferChecked {
+            from: escrow_ata,
+            mint: mint_a,
+            to: maker_ata_a,
+            authority: escrow_account,
+            amount: amount_to_give,
+            decimals: mint_a_decimals,
+            token_program,
+            extra_accounts: remaining_accounts,
+        }
+        .invoke_signed(&[signer.clone()])?;
+
+        CloseAccount {
+            account: escrow_ata,
+            mint: mint_a,
+            destination: maker,
+            authority: escrow_account,
+            token_program,
+        }
+        .invoke_signed(&[signer])?;
     }
-    .invoke_signed(&[signer])?;
 
     let escrow_lamports = escrow_account.lamports();
     escrow_account.set_lamports(0);
@@ -4,9 +4,12 @@ use pinocchio::{
     AccountView, ProgramResult,
 };
 use pinocchio_pubkey::derive_address;
-use pinocchio_token::state::{Mint, TokenAccount};
 
-use crate::state::Escrow;
+use crate::{
+    native::reject_native,
+    state::Escrow,
+    token::{check_token_account, check_token_program, mint_decimals, CloseAccount, TransferChecked},
+};
 
 pub fn process_cancel_instruction_v2(accounts: &[AccountView], _data: &[u8]) -> ProgramResult {
     let [
@@ -17,7 +20,7 @@ pub fn process_cancel_instruction_v2(accounts: &[AccountView], _data: &[u8]) ->
         escrow_ata, 
         system_program, 
         token_program, 
-        _remaining_accounts @ ..
+        remaining_accounts @ ..
     ] = accounts
     else {
         return Err(ProgramError::NotEnoughAccountKeys);
@@ -27,29 +30,20 @@ pub fn process_cancel_instruction_v2(accounts: &[AccountView], _data: &[u8]) ->
         return Err(ProgramError::MissingRequiredSignature);
     }
         
-    if token_program.address() != &pinocchio_token::ID {
-        return Err(ProgramError::IncorrectProgramId);
-    }
+    check_token_program(token_program)?;
     if system_program.address() != &pinocchio_system::ID {
         return Err(ProgramError::IncorrectProgramId);
     }
 
-    let _mint_a_state = Mint::from_account_view(mint_a)?;
+    reject_native(&[mint_a])?;
+    let mint_a_decimals = mint_decimals(mint_a)?;
     unsafe {
         if mint_a.owner() != token_program.address() {
             return Err(ProgramError::IllegalOwner);
         }
     }
 
-    {
-        let maker_ata_a_state = TokenAccount::from_account_view(maker_ata_a)?;
-        if maker_ata_a_state.mint() != mint_a.address() {
-            return Err(ProgramError::InvalidAccountData);
-        }
-        if maker_ata_a_state.owner() != maker.address() {
-            return Err(ProgramError::IllegalOwner);
-        }
-    }
+    check_token_account(maker_ata_a, mint_a, maker.address())?;
 
     let (amount_to_give, bump_bytes, escrow_seed_bytes) = {
         let escrow_state = Escrow::from_account_info_wincode(escrow_account)?;
@@ -68,10 +62,7 @@ pub fn process_cancel_instruction_v2(accounts: &[AccountView], _data: &[u8]) ->
             return Err(ProgramError::InvalidAccountData);
         }
 
-        let escrow_ata_state = TokenAccount::from_account_view(&escrow_ata)?;
-        if escrow_ata_state.owner() != escrow_account.address() {
-            return Err(ProgramError::IllegalOwner);
-        }
+        check_token_account(escrow_ata, mint_a, escrow_account.address())?;
 
         let amount_to_give = escrow_state.amount_to_give();
         let bump_bytes = [escrow_state.bump];
@@ -88,18 +79,24 @@ pub fn process_cancel_instruction_v2(accounts: &[AccountView], _data: &[u8]) ->
     ];
     let signer = Signer::from(&vault_seed);
 
-    pinocchio_token::instructions::Transfer {
+    TransferChecked {
         from: escrow_ata,
+        mint: mint_a,
         to: maker_ata_a,
         authority: escrow_account,
         amount: amount_to_give,
+        decimals: mint_a_decimals,
+        token_program,
+        extra_accounts: remaining_accounts,
     }
     .invoke_signed(&[signer.clone()])?;
 
-    pinocchio_token::instructions::CloseAccount {
+    CloseAccount {
         account: escrow_ata,
+        mint: mint_a,
         destination: maker,
         authority: escrow_account,
+        token_program,
     }
     .invoke_signed(&[signer])?;
 
@@ -1,14 +1,18 @@
 use pinocchio::{
     cpi::{Seed, Signer},
     error::ProgramError,
-    sysvars::{rent::Rent, Sysvar},
-    AccountView, ProgramResult,
+    sysvars::{clock::Clock, rent::Rent, Sysvar},
+    AccountView, Address, ProgramResult,
 };
 use pinocchio_pubkey::derive_address;
-use pinocchio_system::instructions::CreateAccount;
-use pinocchio_token::state::Mint;
+use pinocchio_system::instructions::{CreateAccount, Transfer};
 
-use crate::state::Escrow;
+use crate::{
+    errors::EscrowError,
+    native::is_native,
+    state::Escrow,
+    token::{check_token_account, check_token_program, mint_decimals, TransferChecked},
+};
 
 pub fn process_make_instruction(accounts: &[AccountView], data: &[u8]) -> ProgramResult {
     let [
@@ -20,7 +24,7 @@ pub fn process_make_instruction(accounts: &[AccountView], data: &[u8]) -> Progra
         escrow_ata, 
         system_program, 
         token_program, 
-        _remaining_accounts @ ..
+        remaining_accounts @ ..
     ] = accounts else {
         return Err(ProgramError::NotEnoughAccountKeys);
     };
@@ -32,26 +36,34 @@ pub fn process_make_instruction(accounts: &[AccountView], data: &[u8]) -> Progra
     if system_program.address() != &pinocchio_system::ID {
         return Err(ProgramError::IncorrectProgramId);
     }
-    if token_program.address() != &pinocchio_token::ID {
-        return Err(ProgramError::IncorrectProgramId);
+    check_token_program(token_program)?;
+
+    // Either side may be native SOL, but not both.
+    if is_native(mint_a) && is_native(mint_b) {
+        return Err(ProgramError::InvalidArgument);
     }
 
-    let _mint_a_state = Mint::from_account_view(mint_a)?;
-    let _mint_b_state = Mint::from_account_view(mint_b)?;
-    unsafe {
-        if mint_a.owner() != token_program.address() || mint_b.owner() != token_program.address() {
-            return Err(ProgramError::IllegalOwner);
+    // Mint B may belong to either token program; only its address is stored
+    // here, and Take pays it through its own token program account.
+    let mint_a_decimals = if is_native(mint_a) {
+        None
+    } else {
+        let decimals = mint_decimals(mint_a)?;
+        unsafe {
+            if mint_a.owner() != token_program.address() {
+                return Err(ProgramError::IllegalOwner);
+            }
         }
+
+        check_token_account(maker_ata, mint_a, maker.address())?;
+        Some(decimals)
+    };
+    if !is_native(mint_b) {
+        mint_decimals(mint_b)?;
     }
 
-    {
-        let maker_ata_state = pinocchio_token::state::TokenAccount::from_account_view(&maker_ata)?;
-        if maker_ata_state.owner() != maker.address() {
-            return Err(ProgramError::IllegalOwner);
-        }
-        if maker_ata_state.mint() != mint_a.address() {
-            return Err(ProgramError::InvalidAccountData);
-        }
+    if data.len() < 25 {
+        return Err(ProgramError::InvalidInstructionData);
     }
 
     let escrow_bump = [data[0]];
@@ -72,6 +84,20 @@ pub fn process_make_instruction(accounts: &[AccountView], data: &[u8]) -> Progra
     let amount_to_receive = unsafe { *(data.as_ptr().add(1) as *const u64) };
     let amount_to_give = unsafe { *(data.as_ptr().add(9) as *const u64) };
 
+    // `expires_at` and `allowed_taker` are optional trailing fields; clients
+    // that omit them create public offers that never expire. A tail that is
+    // there but cut short is rejected rather than read as "no expiry".
+    let (expires_at, allowed_taker) = match &data[25..] {
+        [] => (0, None),
+        tail => {
+            let (expires_at, allowed_taker) = tail
+                .split_first_chunk::<8>()
+                .ok_or(ProgramError::InvalidInstructionData)?;
+            (i64::from_le_bytes(*expires_at), read_allowed_taker(Some(allowed_taker))?)
+        }
+    };
+    validate_expires_at(expires_at)?;
+
     let escrow_seed_bytes = escrow_seed.to_le_bytes();
     let seed = [
         Seed::from(b"escrow"),
@@ -93,7 +119,7 @@ pub fn process_make_instruction(accounts: &[AccountView], data: &[u8]) -> Progra
             .invoke_signed(&[seeds])?;
 
             {
-                let escrow_state = Escrow::from_account_info(escrow_account)?;
+                let escrow_state = Escrow::init(escrow_account)?;
 
                 escrow_state.set_maker(maker.address());
                 escrow_state.set_mint_a(mint_a.address());
@@ -101,6 +127,8 @@ pub fn process_make_instruction(accounts: &[AccountView], data: &[u8]) -> Progra
                 escrow_state.set_amount_to_receive(amount_to_receive);
                 escrow_state.set_amount_to_give(amount_to_give);
                 escrow_state.set_seed(escrow_seed);
+                escrow_state.set_expires_at(expires_at);
+                escrow_state.set_allowed_taker(allowed_taker.as_ref());
                 escrow_state.bump = data[0];
             }
         } else {
@@ -108,6 +136,15 @@ pub fn process_make_instruction(accounts: &[AccountView], data: &[u8]) -> Progra
         }
     }
 
+    let Some(mint_a_decimals) = mint_a_decimals else {
+        return Transfer {
+            from: maker,
+            to: escrow_account,
+            lamports: amount_to_give,
+        }
+        .invoke();
+    };
+
     pinocchio_associated_token_account::instructions::Create {
         funding_account: maker,
         account: escrow_ata,
@@ -118,13 +155,40 @@ pub fn process_make_instruction(accounts: &[AccountView], data: &[u8]) -> Progra
     }
     .invoke()?;
 
-    pinocchio_token::instructions::Transfer {
+    TransferChecked {
         from: maker_ata,
+        mint: mint_a,
         to: escrow_ata,
         authority: maker,
         amount: amount_to_give,
+        decimals: mint_a_decimals,
+        token_program,
+        extra_accounts: remaining_accounts,
     }
     .invoke()?;
 
+    // A transfer fee on mint A comes out of the deposit, so the offer is for
+    // whatever actually reached the vault.
+    let deposited = check_token_account(escrow_ata, mint_a, escrow_account.address())?;
+    Escrow::from_account_info(escrow_account)?.set_amount_to_give(deposited);
+
     Ok(())
 }
+
+pub(crate) fn validate_expires_at(expires_at: i64) -> ProgramResult {
+    if expires_at != 0 && expires_at <= Clock::get()?.unix_timestamp {
+        return Err(EscrowError::Expired.into());
+    }
+    Ok(())
+}
+
+pub(crate) fn read_allowed_taker(bytes: Option<&[u8]>) -> Result<Option<Address>, ProgramError> {
+    match bytes {
+        None | Some([]) => Ok(None),
+        Some(bytes) => {
+            let allowed_taker: [u8; 32] =
+                bytes.try_into().map_err(|_| ProgramError::InvalidInstructionData)?;
+            Ok((allowed_taker != [0; 32]).then(|| Address::from(allowed_taker)))
+        }
+    }
+}
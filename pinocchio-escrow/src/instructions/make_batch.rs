@@ -0,0 +1,181 @@
+use pinocchio::{
+    cpi::{Seed, Signer},
+    error::ProgramError,
+    sysvars::{rent::Rent, Sysvar},
+    AccountView, ProgramResult,
+};
+use pinocchio_pubkey::derive_address;
+use pinocchio_system::instructions::CreateAccount;
+
+use crate::{
+    instructions::{read_allowed_taker, validate_expires_at},
+    native::{is_native, reject_native},
+    state::Escrow,
+    token::{check_token_account, check_token_program, mint_decimals, TransferChecked},
+};
+
+use wincode::SchemaRead;
+
+// One offer of the batch. `mint_b` must match the mint account passed with
+// the entry, which is checked the same way Make checks its mint B.
+// `expires_at` of `0` never expires and an all-zero `allowed_taker` lets
+// anyone take the offer.
+#[derive(SchemaRead)]
+pub struct MakeBatchEntry {
+    pub bump: u8,
+    pub seed: u64,
+    pub amount_to_give: u64,
+    pub amount_to_receive: u64,
+    pub mint_b: [u8; 32],
+    pub expires_at: i64,
+    pub allowed_taker: [u8; 32],
+}
+
+impl MakeBatchEntry {
+    pub const LEN: usize = 1 + 8 + 8 + 8 + 32 + 8 + 32;
+}
+
+// Accounts: maker, mint_a, maker_ata, system_program, token_program,
+// associated_token_program, then one (escrow, escrow_ata, mint_b) triple per
+// entry, then any extra accounts mint A's transfer hook needs.
+// Data: the entries back to back, `MakeBatchEntry::LEN` bytes each.
+pub fn process_make_batch_instruction(accounts: &[AccountView], data: &[u8]) -> ProgramResult {
+    let [
+        maker,
+        mint_a,
+        maker_ata,
+        system_program,
+        token_program,
+        _associated_token_program,
+        remaining_accounts @ ..
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !maker.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if system_program.address() != &pinocchio_system::ID {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    check_token_program(token_program)?;
+
+    reject_native(&[mint_a])?;
+    let mint_a_decimals = mint_decimals(mint_a)?;
+    unsafe {
+        if mint_a.owner() != token_program.address() {
+            return Err(ProgramError::IllegalOwner);
+        }
+    }
+
+    check_token_account(maker_ata, mint_a, maker.address())?;
+
+    if data.is_empty() || data.len() % MakeBatchEntry::LEN != 0 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let entries = data.chunks_exact(MakeBatchEntry::LEN);
+    let (escrow_accounts, extra_accounts) = remaining_accounts
+        .split_at_checked(entries.len() * 3)
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+    let rent = Rent::get()?.try_minimum_balance(Escrow::LEN)?;
+
+    for (entry_data, entry_accounts) in entries.zip(escrow_accounts.chunks_exact(3)) {
+        let [escrow_account, escrow_ata, mint_b] = entry_accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        let entry: MakeBatchEntry =
+            wincode::deserialize(entry_data).map_err(|_| ProgramError::InvalidInstructionData)?;
+        validate_expires_at(entry.expires_at)?;
+        let allowed_taker = read_allowed_taker(Some(&entry.allowed_taker))?;
+
+        if mint_b.address().as_array() != &entry.mint_b {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        if !is_native(mint_b) {
+            mint_decimals(mint_b)?;
+        }
+
+        let escrow_bump = [entry.bump];
+        let escrow_seed_bytes = entry.seed.to_le_bytes();
+
+        let seed = [
+            b"escrow",
+            maker.address().as_ref(),
+            &escrow_seed_bytes,
+            &escrow_bump,
+        ];
+        let escrow_account_pda = derive_address(&seed, None, &crate::ID.to_bytes());
+
+        if escrow_account_pda != *escrow_account.address().as_array() {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let seed = [
+            Seed::from(b"escrow"),
+            Seed::from(maker.address().as_array()),
+            Seed::from(&escrow_seed_bytes),
+            Seed::from(&escrow_bump),
+        ];
+        let seeds = Signer::from(&seed);
+
+        unsafe {
+            if escrow_account.owner() != &crate::ID {
+                CreateAccount {
+                    from: maker,
+                    to: escrow_account,
+                    lamports: rent,
+                    space: Escrow::LEN as u64,
+                    owner: &crate::ID,
+                }
+                .invoke_signed(&[seeds])?;
+
+                {
+                    let escrow_state = Escrow::init(escrow_account)?;
+
+                    escrow_state.set_maker(maker.address());
+                    escrow_state.set_mint_a(mint_a.address());
+                    escrow_state.set_mint_b(mint_b.address());
+                    escrow_state.set_amount_to_receive(entry.amount_to_receive);
+                    escrow_state.set_amount_to_give(entry.amount_to_give);
+                    escrow_state.set_seed(entry.seed);
+                    escrow_state.set_expires_at(entry.expires_at);
+                    escrow_state.set_allowed_taker(allowed_taker.as_ref());
+                    escrow_state.bump = entry.bump;
+                }
+            } else {
+                return Err(ProgramError::IllegalOwner);
+            }
+        }
+
+        pinocchio_associated_token_account::instructions::Create {
+            funding_account: maker,
+            account: escrow_ata,
+            wallet: escrow_account,
+            mint: mint_a,
+            token_program: token_program,
+            system_program: system_program,
+        }
+        .invoke()?;
+
+        TransferChecked {
+            from: maker_ata,
+            mint: mint_a,
+            to: escrow_ata,
+            authority: maker,
+            amount: entry.amount_to_give,
+            decimals: mint_a_decimals,
+            token_program,
+            extra_accounts,
+        }
+        .invoke()?;
+
+        // As in `Make`, the offer is for what reached the vault after fees.
+        let deposited = check_token_account(escrow_ata, mint_a, escrow_account.address())?;
+        Escrow::from_account_info(escrow_account)?.set_amount_to_give(deposited);
+    }
+
+    Ok(())
+}
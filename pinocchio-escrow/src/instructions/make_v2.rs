@@ -6,9 +6,13 @@ use pinocchio::{
 };
 use pinocchio_pubkey::derive_address;
 use pinocchio_system::instructions::CreateAccount;
-use pinocchio_token::state::Mint;
 
-use crate::state::Escrow;
+use crate::{
+    instructions::{read_allowed_taker, validate_expires_at},
+    native::reject_native,
+    state::Escrow,
+    token::{check_token_account, check_token_program, mint_decimals, TransferChecked},
+};
 
 use wincode::SchemaRead;
 
@@ -20,6 +24,10 @@ pub struct MakeInstructionData {
     pub seed: u64,
 }
 
+impl MakeInstructionData {
+    pub const LEN: usize = 1 + 8 + 8 + 8;
+}
+
 pub fn process_make_instruction_v2(accounts: &[AccountView], data: &[u8]) -> ProgramResult {
     let [
         maker, 
@@ -30,7 +38,7 @@ pub fn process_make_instruction_v2(accounts: &[AccountView], data: &[u8]) -> Pro
         escrow_ata, 
         system_program, 
         token_program, 
-        _remaining_accounts @ ..
+        remaining_accounts @ ..
     ] = accounts else {
         return Err(ProgramError::NotEnoughAccountKeys);
     };
@@ -42,30 +50,41 @@ pub fn process_make_instruction_v2(accounts: &[AccountView], data: &[u8]) -> Pro
     if system_program.address() != &pinocchio_system::ID {
         return Err(ProgramError::IncorrectProgramId);
     }
-    if token_program.address() != &pinocchio_token::ID {
-        return Err(ProgramError::IncorrectProgramId);
-    }
+    check_token_program(token_program)?;
 
-    let _mint_a_state = Mint::from_account_view(mint_a)?;
-    let _mint_b_state = Mint::from_account_view(mint_b)?;
+    // Mint B may belong to either token program; only its address is stored
+    // here, and Take pays it through its own token program account.
+    reject_native(&[mint_a, mint_b])?;
+    let mint_a_decimals = mint_decimals(mint_a)?;
+    mint_decimals(mint_b)?;
     unsafe {
-        if mint_a.owner() != token_program.address() || mint_b.owner() != token_program.address() {
+        if mint_a.owner() != token_program.address() {
             return Err(ProgramError::IllegalOwner);
         }
     }
 
-    {
-        let maker_ata_state = pinocchio_token::state::TokenAccount::from_account_view(&maker_ata)?;
-        if maker_ata_state.owner() != maker.address() {
-            return Err(ProgramError::IllegalOwner);
-        }
-        if maker_ata_state.mint() != mint_a.address() {
-            return Err(ProgramError::InvalidAccountData);
-        }
-    }
+    check_token_account(maker_ata, mint_a, maker.address())?;
 
+    let (ix_data, trailing) = data
+        .split_at_checked(MakeInstructionData::LEN)
+        .ok_or(ProgramError::InvalidInstructionData)?;
     let ix_data: MakeInstructionData =
-        wincode::deserialize(data).map_err(|_| ProgramError::InvalidInstructionData)?;
+        wincode::deserialize(ix_data).map_err(|_| ProgramError::InvalidInstructionData)?;
+
+    // Optional trailing `expires_at` and `allowed_taker`, as in `Make`.
+    let (expires_at, allowed_taker) = match trailing.split_at_checked(8) {
+        None if trailing.is_empty() => (0, None),
+        None => return Err(ProgramError::InvalidInstructionData),
+        Some((expires_at, allowed_taker)) => (
+            i64::from_le_bytes(
+                expires_at
+                    .try_into()
+                    .map_err(|_| ProgramError::InvalidInstructionData)?,
+            ),
+            read_allowed_taker(Some(allowed_taker))?,
+        ),
+    };
+    validate_expires_at(expires_at)?;
 
     let escrow_bump = [ix_data.bump];
     let amount_to_receive = ix_data.amount_to_receive;
@@ -106,7 +125,7 @@ pub fn process_make_instruction_v2(accounts: &[AccountView], data: &[u8]) -> Pro
             .invoke_signed(&[seeds])?;
 
             {
-                let escrow_state = Escrow::from_account_info(escrow_account)?;
+                let escrow_state = Escrow::init(escrow_account)?;
 
                 escrow_state.set_maker(maker.address());
                 escrow_state.set_mint_a(mint_a.address());
@@ -114,6 +133,8 @@ pub fn process_make_instruction_v2(accounts: &[AccountView], data: &[u8]) -> Pro
                 escrow_state.set_amount_to_receive(amount_to_receive);
                 escrow_state.set_amount_to_give(amount_to_give);
                 escrow_state.set_seed(escrow_seed);
+                escrow_state.set_expires_at(expires_at);
+                escrow_state.set_allowed_taker(allowed_taker.as_ref());
                 escrow_state.bump = ix_data.bump;
             }
         } else {
@@ -131,13 +152,22 @@ pub fn process_make_instruction_v2(accounts: &[AccountView], data: &[u8]) -> Pro
     }
     .invoke()?;
 
-    pinocchio_token::instructions::Transfer {
+    TransferChecked {
         from: maker_ata,
+        mint: mint_a,
         to: escrow_ata,
         authority: maker,
         amount: amount_to_give,
+        decimals: mint_a_decimals,
+        token_program,
+        extra_accounts: remaining_accounts,
     }
     .invoke()?;
 
+    // A transfer fee on mint A comes out of the deposit, so the offer is for
+    // whatever actually reached the vault.
+    let deposited = check_token_account(escrow_ata, mint_a, escrow_account.address())?;
+    Escrow::from_account_info(escrow_account)?.set_amount_to_give(deposited);
+
     Ok(())
 }
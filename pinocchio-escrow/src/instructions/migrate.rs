@@ -0,0 +1,78 @@
+use pinocchio::{
+    error::ProgramError,
+    sysvars::{rent::Rent, Sysvar},
+    AccountView, ProgramResult,
+};
+use pinocchio_pubkey::derive_address;
+use pinocchio_system::instructions::Transfer;
+
+use crate::state::{Escrow, LegacyEscrow};
+
+// Rewrites an escrow created before the account was versioned into the
+// current layout, so the other instructions, which only read that layout,
+// can use it again. Fields the old layout lacked take their defaults: no
+// expiry unless one was stored, and no allowed taker. Anyone may migrate an
+// escrow; `payer` covers the rent for the larger account.
+pub fn process_migrate_instruction(accounts: &[AccountView], _data: &[u8]) -> ProgramResult {
+    let [payer, escrow_account, system_program] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !payer.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if system_program.address() != &pinocchio_system::ID {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    unsafe {
+        if escrow_account.owner() != &crate::ID {
+            return Err(ProgramError::IllegalOwner);
+        }
+    }
+
+    let (legacy, legacy_len) = {
+        let data = escrow_account.try_borrow()?;
+        (LegacyEscrow::read(&data)?, data.len())
+    };
+
+    let seeds = [
+        b"escrow",
+        legacy.maker.as_ref(),
+        &legacy.seed.to_le_bytes(),
+        &[legacy.bump],
+    ];
+    let escrow_account_pda = derive_address(&seeds, None, &crate::ID.as_array());
+    if escrow_account_pda != *escrow_account.address().as_array() {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let rent = Rent::get()?;
+    let top_up = rent
+        .try_minimum_balance(Escrow::LEN)?
+        .saturating_sub(rent.try_minimum_balance(legacy_len)?);
+    if top_up > 0 {
+        Transfer {
+            from: payer,
+            to: escrow_account,
+            lamports: top_up,
+        }
+        .invoke()?;
+    }
+
+    escrow_account.resize(Escrow::LEN)?;
+
+    let escrow_state = Escrow::init(escrow_account)?;
+    escrow_state.set_maker(&legacy.maker);
+    escrow_state.set_mint_a(&legacy.mint_a);
+    escrow_state.set_mint_b(&legacy.mint_b);
+    escrow_state.set_amount_to_receive(legacy.amount_to_receive);
+    escrow_state.set_amount_to_give(legacy.amount_to_give);
+    escrow_state.set_seed(legacy.seed);
+    escrow_state.set_expires_at(legacy.expires_at);
+    escrow_state.set_allowed_taker(None);
+    escrow_state.bump = legacy.bump;
+
+    Ok(())
+}
@@ -1,15 +1,25 @@
+pub mod amend;
 pub mod cancel;
 pub mod cancel_v2;
 pub mod make;
+pub mod make_batch;
 pub mod make_v2;
+pub mod migrate;
+pub mod reclaim;
 pub mod take;
+pub mod take_partial;
 pub mod take_v2;
 
+pub use amend::*;
 pub use cancel::*;
 pub use cancel_v2::*;
 pub use make::*;
+pub use make_batch::*;
 pub use make_v2::*;
+pub use migrate::*;
+pub use reclaim::*;
 pub use take::*;
+pub use take_partial::*;
 pub use take_v2::*;
 
 use pinocchio::error::ProgramError;
@@ -22,6 +32,11 @@ pub enum EscrowInstructions {
     MakeV2 = 3,
     TakeV2 = 4,
     CancelV2 = 5,
+    MakeBatch = 6,
+    TakePartial = 7,
+    Reclaim = 8,
+    Amend = 9,
+    Migrate = 10,
 }
 
 impl TryFrom<&u8> for EscrowInstructions {
@@ -35,6 +50,11 @@ impl TryFrom<&u8> for EscrowInstructions {
             3 => Ok(EscrowInstructions::MakeV2),
             4 => Ok(EscrowInstructions::TakeV2),
             5 => Ok(EscrowInstructions::CancelV2),
+            6 => Ok(EscrowInstructions::MakeBatch),
+            7 => Ok(EscrowInstructions::TakePartial),
+            8 => Ok(EscrowInstructions::Reclaim),
+            9 => Ok(EscrowInstructions::Amend),
+            10 => Ok(EscrowInstructions::Migrate),
             _ => Err(ProgramError::InvalidInstructionData),
         }
     }
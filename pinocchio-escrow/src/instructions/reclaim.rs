@@ -0,0 +1,148 @@
+use pinocchio::{
+    cpi::{Seed, Signer},
+    error::ProgramError,
+    sysvars::{clock::Clock, Sysvar},
+    AccountView, ProgramResult,
+};
+use pinocchio_associated_token_account::instructions::Create;
+use pinocchio_pubkey::derive_address;
+
+use crate::{
+    errors::EscrowError,
+    native::is_native,
+    state::Escrow,
+    token::{check_token_account, check_token_program, mint_decimals, CloseAccount, TransferChecked},
+};
+
+// Permissionless cleanup of an expired offer: anyone can return the deposit
+// to the maker's ATA (creating it at their own cost if needed) and the rent
+// of both accounts to the maker. The maker does not need to sign.
+pub fn process_reclaim_instruction(accounts: &[AccountView], _data: &[u8]) -> ProgramResult {
+    let [
+        caller,
+        maker,
+        mint_a,
+        maker_ata_a,
+        escrow_account,
+        escrow_ata,
+        system_program,
+        token_program,
+        remaining_accounts @ ..
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !caller.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    check_token_program(token_program)?;
+    if system_program.address() != &pinocchio_system::ID {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let mint_a_decimals = if is_native(mint_a) {
+        None
+    } else {
+        let decimals = mint_decimals(mint_a)?;
+        unsafe {
+            if mint_a.owner() != token_program.address() {
+                return Err(ProgramError::IllegalOwner);
+            }
+        }
+
+        if maker_ata_a.data_len() == 0 {
+            Create {
+                funding_account: caller,
+                account: maker_ata_a,
+                wallet: maker,
+                mint: mint_a,
+                token_program,
+                system_program,
+            }
+            .invoke()?;
+        } else {
+            check_token_account(maker_ata_a, mint_a, maker.address())?;
+        }
+
+        Some(decimals)
+    };
+
+    let (amount_to_give, bump_bytes, escrow_seed_bytes) = {
+        let escrow_state = Escrow::from_account_info(escrow_account)?;
+        let seeds = [
+            b"escrow",
+            maker.address().as_ref(),
+            &escrow_state.seed().to_le_bytes(),
+            &[escrow_state.bump],
+        ];
+        let escrow_account_pda = derive_address(&seeds, None, &crate::ID.as_array());
+
+        if escrow_state.maker() != *maker.address()
+            || escrow_state.mint_a() != *mint_a.address()
+            || escrow_account_pda != *escrow_account.address().as_array()
+        {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if !escrow_state.is_expired(Clock::get()?.unix_timestamp) {
+            return Err(EscrowError::NotExpired.into());
+        }
+
+        if mint_a_decimals.is_some() {
+            check_token_account(escrow_ata, mint_a, escrow_account.address())?;
+        }
+
+        let amount_to_give = escrow_state.amount_to_give();
+        let bump_bytes = [escrow_state.bump];
+        let escrow_seed_bytes = escrow_state.seed().to_le_bytes();
+
+        (amount_to_give, bump_bytes, escrow_seed_bytes)
+    };
+
+    // Native SOL needs no transfer: the deposit is the escrow's balance above
+    // rent, all of which the close below hands to the maker.
+    if let Some(mint_a_decimals) = mint_a_decimals {
+        let vault_seed = [
+            Seed::from(b"escrow"),
+            Seed::from(maker.address().as_array()),
+            Seed::from(&escrow_seed_bytes),
+            Seed::from(&bump_bytes),
+        ];
+        let signer = Signer::from(&vault_seed);
+
+        TransferChecked {
+            from: escrow_ata,
+            mint: mint_a,
+            to: maker_ata_a,
+            authority: escrow_account,
+            amount: amount_to_give,
+            decimals: mint_a_decimals,
+            token_program,
+            extra_accounts: remaining_accounts,
+        }
+        .invoke_signed(&[signer.clone()])?;
+
+        CloseAccount {
+            account: escrow_ata,
+            mint: mint_a,
+            destination: maker,
+            authority: escrow_account,
+            token_program,
+        }
+        .invoke_signed(&[signer])?;
+    }
+
+    let escrow_lamports = escrow_account.lamports();
+    escrow_account.set_lamports(0);
+    maker.set_lamports(
+        maker
+            .lamports()
+            .checked_add(escrow_lamports)
+            .ok_or(ProgramError::ArithmeticOverflow)?,
+    );
+
+    escrow_account.close()?;
+
+    Ok(())
+}
@@ -1,15 +1,62 @@
 use pinocchio::{
     cpi::{Seed, Signer},
     error::ProgramError,
+    sysvars::{clock::Clock, Sysvar},
     AccountView, ProgramResult,
 };
 use pinocchio_associated_token_account::instructions::Create;
 use pinocchio_pubkey::derive_address;
-use pinocchio_token::state::{Mint, TokenAccount};
+use pinocchio_system::instructions::Transfer;
+use wincode::SchemaRead;
 
-use crate::state::Escrow;
+use crate::{
+    errors::EscrowError,
+    native::{is_native, transfer_lamports},
+    state::Escrow,
+    token::{
+        amount_with_transfer_fee, check_token_account, check_token_program, mint_decimals,
+        CloseAccount, TransferChecked,
+    },
+};
+
+// The offer as the taker saw it when signing. `Amend` can reprice an open
+// escrow, so a take that carries these fails with `OfferChanged` unless the
+// escrow still holds exactly this offer. They are optional: without them the
+// taker accepts whatever the escrow holds when the take lands.
+#[derive(SchemaRead)]
+pub struct ExpectedOffer {
+    pub amount_to_receive: u64,
+    pub amount_to_give: u64,
+}
+
+impl ExpectedOffer {
+    pub const LEN: usize = 8 + 8;
+
+    pub(crate) fn read(data: &[u8]) -> Result<Option<Self>, ProgramError> {
+        match data.len() {
+            0 => Ok(None),
+            Self::LEN => wincode::deserialize(data)
+                .map(Some)
+                .map_err(|_| ProgramError::InvalidInstructionData),
+            _ => Err(ProgramError::InvalidInstructionData),
+        }
+    }
+
+    pub(crate) fn check(&self, escrow: &Escrow) -> ProgramResult {
+        if escrow.amount_to_receive() != self.amount_to_receive
+            || escrow.amount_to_give() != self.amount_to_give
+        {
+            return Err(EscrowError::OfferChanged.into());
+        }
+        Ok(())
+    }
+}
 
-pub fn process_take_instruction(accounts: &[AccountView], _data: &[u8]) -> ProgramResult {
+// Takes a token program per leg, mint A's then mint B's, even when both mints
+// share one. Clients from before Token-2022 support passed only one and need
+// to add `token_program_b` before the remaining accounts; the same holds for
+// TakeV2 and TakePartial.
+pub fn process_take_instruction(accounts: &[AccountView], data: &[u8]) -> ProgramResult {
     let [
         taker, 
         maker, 
@@ -21,8 +68,9 @@ pub fn process_take_instruction(accounts: &[AccountView], _data: &[u8]) -> Progr
         escrow_account, 
         escrow_ata, 
         system_program, 
-        token_program, 
-        _remaining_accounts @ ..
+        token_program_a, 
+        token_program_b, 
+        remaining_accounts @ ..
     ] = accounts else {
         return Err(ProgramError::NotEnoughAccountKeys);
     };
@@ -31,28 +79,23 @@ pub fn process_take_instruction(accounts: &[AccountView], _data: &[u8]) -> Progr
         return Err(ProgramError::MissingRequiredSignature);
     }
 
+    let expected_offer = ExpectedOffer::read(data)?;
+
     if system_program.address() != &pinocchio_system::ID {
         return Err(ProgramError::IncorrectProgramId);
     }
-    if token_program.address() != &pinocchio_token::ID {
-        return Err(ProgramError::IncorrectProgramId);
-    }
-
-    let _mint_a_state = Mint::from_account_view(mint_a)?;
-    let _mint_b_state = Mint::from_account_view(mint_b)?;
-    unsafe {
-        if mint_a.owner() != token_program.address() || mint_b.owner() != token_program.address() {
-            return Err(ProgramError::IllegalOwner);
-        }
-    }
+    check_token_program(token_program_a)?;
+    check_token_program(token_program_b)?;
 
-    {
-        let taker_ata_b_state = TokenAccount::from_account_view(&taker_ata_b)?;
-        if taker_ata_b_state.owner() != taker.address() {
-            return Err(ProgramError::IllegalOwner);
-        }
-        if taker_ata_b_state.mint() != mint_b.address() {
-            return Err(ProgramError::InvalidAccountData);
+    // A native SOL leg has no decimals and its token accounts are not used.
+    let mint_a_decimals = if is_native(mint_a) {
+        None
+    } else {
+        let decimals = mint_decimals(mint_a)?;
+        unsafe {
+            if mint_a.owner() != token_program_a.address() {
+                return Err(ProgramError::IllegalOwner);
+            }
         }
 
         if taker_ata_a.data_len() == 0 {
@@ -61,40 +104,45 @@ pub fn process_take_instruction(accounts: &[AccountView], _data: &[u8]) -> Progr
                 account: taker_ata_a,
                 wallet: taker,
                 mint: mint_a,
-                token_program,
+                token_program: token_program_a,
                 system_program,
             }
             .invoke()?;
         } else {
-            let taker_ata_a_state = TokenAccount::from_account_view(taker_ata_a)?;
-            if taker_ata_a_state.mint() != mint_a.address() {
-                return Err(ProgramError::InvalidAccountData);
-            }
-            if taker_ata_a_state.owner() != taker.address() {
+            check_token_account(taker_ata_a, mint_a, taker.address())?;
+        }
+
+        Some(decimals)
+    };
+
+    let mint_b_decimals = if is_native(mint_b) {
+        None
+    } else {
+        let decimals = mint_decimals(mint_b)?;
+        unsafe {
+            if mint_b.owner() != token_program_b.address() {
                 return Err(ProgramError::IllegalOwner);
             }
         }
 
+        check_token_account(taker_ata_b, mint_b, taker.address())?;
+
         if maker_ata_b.data_len() == 0 {
             Create {
                 funding_account: taker,
                 account: maker_ata_b,
                 wallet: maker,
                 mint: mint_b,
-                token_program,
+                token_program: token_program_b,
                 system_program,
             }
             .invoke()?;
         } else {
-            let maker_ata_b_state = TokenAccount::from_account_view(maker_ata_b)?;
-            if maker_ata_b_state.mint() != mint_b.address() {
-                return Err(ProgramError::InvalidAccountData);
-            }
-            if maker_ata_b_state.owner() != maker.address() {
-                return Err(ProgramError::IllegalOwner);
-            }
+            check_token_account(maker_ata_b, mint_b, maker.address())?;
         }
-    }
+
+        Some(decimals)
+    };
 
     let (amount_to_give, amount_to_receive, bump_bytes, escrow_seed_bytes) = {
         let escrow_state = Escrow::from_account_info(escrow_account)?;
@@ -114,9 +162,18 @@ pub fn process_take_instruction(accounts: &[AccountView], _data: &[u8]) -> Progr
             return Err(ProgramError::InvalidAccountData);
         }
 
-        let escrow_ata_state = TokenAccount::from_account_view(&escrow_ata)?;
-        if escrow_ata_state.owner() != escrow_account.address() {
-            return Err(ProgramError::IllegalOwner);
+        if escrow_state.is_expired(Clock::get()?.unix_timestamp) {
+            return Err(EscrowError::Expired.into());
+        }
+        if !escrow_state.can_be_taken_by(taker.address()) {
+            return Err(EscrowError::InvalidTaker.into());
+        }
+        if let Some(expected_offer) = &expected_offer {
+            expected_offer.check(escrow_state)?;
+        }
+
+        if mint_a_decimals.is_some() {
+            check_token_account(escrow_ata, mint_a, escrow_account.address())?;
         }
 
         let amount_to_give = escrow_state.amount_to_give();
@@ -132,36 +189,63 @@ pub fn process_take_instruction(accounts: &[AccountView], _data: &[u8]) -> Progr
         )
     };
 
-    pinocchio_token::instructions::Transfer {
-        from: taker_ata_b,
-        to: maker_ata_b,
-        authority: taker,
-        amount: amount_to_receive,
-    }
-    .invoke()?;
-
-    let vault_seed = [
-        Seed::from(b"escrow"),
-        Seed::from(maker.address().as_array()),
-        Seed::from(&escrow_seed_bytes),
-        Seed::from(&bump_bytes),
-    ];
-    let signer = Signer::from(&vault_seed);
-
-    pinocchio_token::instructions::Transfer {
-        from: escrow_ata,
-        to: taker_ata_a,
-        authority: escrow_account,
-        amount: amount_to_give,
+    match mint_b_decimals {
+        // The taker covers any transfer fee on mint B, so the maker receives
+        // the full amount.
+        Some(mint_b_decimals) => TransferChecked {
+            from: taker_ata_b,
+            mint: mint_b,
+            to: maker_ata_b,
+            authority: taker,
+            amount: amount_with_transfer_fee(mint_b, amount_to_receive)?,
+            decimals: mint_b_decimals,
+            token_program: token_program_b,
+            extra_accounts: remaining_accounts,
+        }
+        .invoke()?,
+        None => Transfer {
+            from: taker,
+            to: maker,
+            lamports: amount_to_receive,
+        }
+        .invoke()?,
     }
-    .invoke_signed(&[signer.clone()])?;
 
-    pinocchio_token::instructions::CloseAccount {
-        account: escrow_ata,
-        destination: maker,
-        authority: escrow_account,
+    match mint_a_decimals {
+        Some(mint_a_decimals) => {
+            let vault_seed = [
+                Seed::from(b"escrow"),
+                Seed::from(maker.address().as_array()),
+                Seed::from(&escrow_seed_bytes),
+                Seed::from(&bump_bytes),
+            ];
+            let signer = Signer::from(&vault_seed);
+
+            TransferChecked {
+                from: escrow_ata,
+                mint: mint_a,
+                to: taker_ata_a,
+                authority: escrow_account,
+                amount: amount_to_give,
+                decimals: mint_a_decimals,
+                token_program: token_program_a,
+                extra_accounts: remaining_accounts,
+            }
+            .invoke_signed(&[signer.clone()])?;
+
+            CloseAccount {
+                account: escrow_ata,
+                mint: mint_a,
+                destination: maker,
+                authority: escrow_account,
+                token_program: token_program_a,
+            }
+            .invoke_signed(&[signer])?;
+        }
+        // The deposit is held by the escrow itself; the rent left after it
+        // goes back to the maker when the escrow is closed below.
+        None => transfer_lamports(escrow_account, taker, amount_to_give)?,
     }
-    .invoke_signed(&[signer])?;
 
     let escrow_lamports = escrow_account.lamports();
     escrow_account.set_lamports(0);
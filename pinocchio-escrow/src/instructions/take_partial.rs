@@ -0,0 +1,224 @@
+use pinocchio::{
+    cpi::{Seed, Signer},
+    error::ProgramError,
+    sysvars::{clock::Clock, Sysvar},
+    AccountView, ProgramResult,
+};
+use pinocchio_associated_token_account::instructions::Create;
+use pinocchio_pubkey::derive_address;
+
+use crate::{
+    errors::EscrowError,
+    instructions::ExpectedOffer,
+    native::reject_native,
+    state::Escrow,
+    token::{
+        amount_with_transfer_fee, check_token_account, check_token_program, mint_decimals,
+        CloseAccount, TransferChecked,
+    },
+};
+
+// Fills part of an offer: paying `amount_b` of mint B releases
+// `amount_b / amount_to_receive` of the remaining deposit. The payout is
+// rounded down, so rounding dust stays in the vault and goes to whoever
+// fills the rest of `amount_to_receive`, which closes the escrow like `Take`.
+// Data: `amount_b`, then an optional `ExpectedOffer` for the offer as it
+// stands before this fill.
+pub fn process_take_partial_instruction(accounts: &[AccountView], data: &[u8]) -> ProgramResult {
+    let [
+        taker, 
+        maker, 
+        mint_a, 
+        mint_b, 
+        taker_ata_a, 
+        taker_ata_b, 
+        maker_ata_b, 
+        escrow_account, 
+        escrow_ata, 
+        system_program, 
+        token_program_a, 
+        token_program_b, 
+        remaining_accounts @ ..
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !taker.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let (amount_b, expected_offer) = data
+        .split_first_chunk::<8>()
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    let amount_b = u64::from_le_bytes(*amount_b);
+    let expected_offer = ExpectedOffer::read(expected_offer)?;
+    if amount_b == 0 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    if system_program.address() != &pinocchio_system::ID {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    check_token_program(token_program_a)?;
+    check_token_program(token_program_b)?;
+
+    reject_native(&[mint_a, mint_b])?;
+    let mint_a_decimals = mint_decimals(mint_a)?;
+    let mint_b_decimals = mint_decimals(mint_b)?;
+    unsafe {
+        if mint_a.owner() != token_program_a.address()
+            || mint_b.owner() != token_program_b.address()
+        {
+            return Err(ProgramError::IllegalOwner);
+        }
+    }
+
+    {
+        check_token_account(taker_ata_b, mint_b, taker.address())?;
+
+        if taker_ata_a.data_len() == 0 {
+            Create {
+                funding_account: taker,
+                account: taker_ata_a,
+                wallet: taker,
+                mint: mint_a,
+                token_program: token_program_a,
+                system_program,
+            }
+            .invoke()?;
+        } else {
+            check_token_account(taker_ata_a, mint_a, taker.address())?;
+        }
+
+        if maker_ata_b.data_len() == 0 {
+            Create {
+                funding_account: taker,
+                account: maker_ata_b,
+                wallet: maker,
+                mint: mint_b,
+                token_program: token_program_b,
+                system_program,
+            }
+            .invoke()?;
+        } else {
+            check_token_account(maker_ata_b, mint_b, maker.address())?;
+        }
+    }
+
+    let (payout, is_full_fill, bump_bytes, escrow_seed_bytes) = {
+        let escrow_state = Escrow::from_account_info(escrow_account)?;
+        let seeds = [
+            b"escrow",
+            maker.address().as_ref(),
+            &escrow_state.seed().to_le_bytes(),
+            &[escrow_state.bump],
+        ];
+        let escrow_account_pda = derive_address(&seeds, None, &crate::ID.as_array());
+
+        if escrow_state.maker() != *maker.address()
+            || escrow_state.mint_a() != *mint_a.address()
+            || escrow_state.mint_b() != *mint_b.address()
+            || escrow_account_pda != *escrow_account.address().as_array()
+        {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if escrow_state.is_expired(Clock::get()?.unix_timestamp) {
+            return Err(EscrowError::Expired.into());
+        }
+        if !escrow_state.can_be_taken_by(taker.address()) {
+            return Err(EscrowError::InvalidTaker.into());
+        }
+        if let Some(expected_offer) = &expected_offer {
+            expected_offer.check(escrow_state)?;
+        }
+
+        check_token_account(escrow_ata, mint_a, escrow_account.address())?;
+
+        let amount_to_give = escrow_state.amount_to_give();
+        let amount_to_receive = escrow_state.amount_to_receive();
+        if amount_b > amount_to_receive {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let is_full_fill = amount_b == amount_to_receive;
+        let payout = if is_full_fill {
+            amount_to_give
+        } else {
+            (amount_b as u128 * amount_to_give as u128 / amount_to_receive as u128) as u64
+        };
+        if payout == 0 {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if !is_full_fill {
+            escrow_state.set_amount_to_give(amount_to_give - payout);
+            escrow_state.set_amount_to_receive(amount_to_receive - amount_b);
+        }
+
+        let bump_bytes = [escrow_state.bump];
+        let escrow_seed_bytes = escrow_state.seed().to_le_bytes();
+
+        (payout, is_full_fill, bump_bytes, escrow_seed_bytes)
+    };
+
+    // The taker covers any transfer fee on mint B, so the maker receives the
+    // full amount.
+    TransferChecked {
+        from: taker_ata_b,
+        mint: mint_b,
+        to: maker_ata_b,
+        authority: taker,
+        amount: amount_with_transfer_fee(mint_b, amount_b)?,
+        decimals: mint_b_decimals,
+        token_program: token_program_b,
+        extra_accounts: remaining_accounts,
+    }
+    .invoke()?;
+
+    let vault_seed = [
+        Seed::from(b"escrow"),
+        Seed::from(maker.address().as_array()),
+        Seed::from(&escrow_seed_bytes),
+        Seed::from(&bump_bytes),
+    ];
+    let signer = Signer::from(&vault_seed);
+
+    TransferChecked {
+        from: escrow_ata,
+        mint: mint_a,
+        to: taker_ata_a,
+        authority: escrow_account,
+        amount: payout,
+        decimals: mint_a_decimals,
+        token_program: token_program_a,
+        extra_accounts: remaining_accounts,
+    }
+    .invoke_signed(&[signer.clone()])?;
+
+    if !is_full_fill {
+        return Ok(());
+    }
+
+    CloseAccount {
+        account: escrow_ata,
+        mint: mint_a,
+        destination: maker,
+        authority: escrow_account,
+        token_program: token_program_a,
+    }
+    .invoke_signed(&[signer])?;
+
+    let escrow_lamports = escrow_account.lamports();
+    escrow_account.set_lamports(0);
+    maker.set_lamports(
+        maker
+            .lamports()
+            .checked_add(escrow_lamports)
+            .ok_or(ProgramError::ArithmeticOverflow)?,
+    );
+
+    escrow_account.close()?;
+
+    Ok(())
+}
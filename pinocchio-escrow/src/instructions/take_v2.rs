@@ -1,15 +1,25 @@
 use pinocchio::{
     cpi::{Seed, Signer},
     error::ProgramError,
+    sysvars::{clock::Clock, Sysvar},
     AccountView, ProgramResult,
 };
 use pinocchio_associated_token_account::instructions::Create;
 use pinocchio_pubkey::derive_address;
-use pinocchio_token::state::{Mint, TokenAccount};
 
-use crate::state::Escrow;
+use crate::{
+    errors::EscrowError,
+    instructions::ExpectedOffer,
+    native::reject_native,
+    state::Escrow,
+    token::{
+        amount_with_transfer_fee, check_token_account, check_token_program, mint_decimals,
+        CloseAccount, TransferChecked,
+    },
+};
 
-pub fn process_take_instruction_v2(accounts: &[AccountView], _data: &[u8]) -> ProgramResult {
+// Data: an optional `ExpectedOffer`, as in `Take`.
+pub fn process_take_instruction_v2(accounts: &[AccountView], data: &[u8]) -> ProgramResult {
     let [
         taker, 
         maker, 
@@ -21,8 +31,9 @@ pub fn process_take_instruction_v2(accounts: &[AccountView], _data: &[u8]) -> Pr
         escrow_account, 
         escrow_ata, 
         system_program, 
-        token_program, 
-        _remaining_accounts @ ..
+        token_program_a, 
+        token_program_b, 
+        remaining_accounts @ ..
     ] = accounts else {
         return Err(ProgramError::NotEnoughAccountKeys);
     };
@@ -30,30 +41,28 @@ pub fn process_take_instruction_v2(accounts: &[AccountView], _data: &[u8]) -> Pr
     if !taker.is_signer() {
         return Err(ProgramError::MissingRequiredSignature);
     }
+
+    let expected_offer = ExpectedOffer::read(data)?;
     
     if system_program.address() != &pinocchio_system::ID {
         return Err(ProgramError::IncorrectProgramId);
     }
-    if token_program.address() != &pinocchio_token::ID {
-        return Err(ProgramError::IncorrectProgramId);
-    }
+    check_token_program(token_program_a)?;
+    check_token_program(token_program_b)?;
 
-    let _mint_a_state = Mint::from_account_view(mint_a)?;
-    let _mint_b_state = Mint::from_account_view(mint_b)?;
+    reject_native(&[mint_a, mint_b])?;
+    let mint_a_decimals = mint_decimals(mint_a)?;
+    let mint_b_decimals = mint_decimals(mint_b)?;
     unsafe {
-        if mint_a.owner() != token_program.address() || mint_b.owner() != token_program.address() {
+        if mint_a.owner() != token_program_a.address()
+            || mint_b.owner() != token_program_b.address()
+        {
             return Err(ProgramError::IllegalOwner);
         }
     }
 
     {
-        let taker_ata_b_state = TokenAccount::from_account_view(&taker_ata_b)?;
-        if taker_ata_b_state.owner() != taker.address() {
-            return Err(ProgramError::IllegalOwner);
-        }
-        if taker_ata_b_state.mint() != mint_b.address() {
-            return Err(ProgramError::InvalidAccountData);
-        }
+        check_token_account(taker_ata_b, mint_b, taker.address())?;
 
         if taker_ata_a.data_len() == 0 {
             Create {
@@ -61,18 +70,12 @@ pub fn process_take_instruction_v2(accounts: &[AccountView], _data: &[u8]) -> Pr
                 account: taker_ata_a,
                 wallet: taker,
                 mint: mint_a,
-                token_program,
+                token_program: token_program_a,
                 system_program,
             }
             .invoke()?;
         } else {
-            let taker_ata_a_state = TokenAccount::from_account_view(taker_ata_a)?;
-            if taker_ata_a_state.mint() != mint_a.address() {
-                return Err(ProgramError::InvalidAccountData);
-            }
-            if taker_ata_a_state.owner() != taker.address() {
-                return Err(ProgramError::IllegalOwner);
-            }
+            check_token_account(taker_ata_a, mint_a, taker.address())?;
         }
 
         if maker_ata_b.data_len() == 0 {
@@ -81,18 +84,12 @@ pub fn process_take_instruction_v2(accounts: &[AccountView], _data: &[u8]) -> Pr
                 account: maker_ata_b,
                 wallet: maker,
                 mint: mint_b,
-                token_program,
+                token_program: token_program_b,
                 system_program,
             }
             .invoke()?;
         } else {
-            let maker_ata_b_state = TokenAccount::from_account_view(maker_ata_b)?;
-            if maker_ata_b_state.mint() != mint_b.address() {
-                return Err(ProgramError::InvalidAccountData);
-            }
-            if maker_ata_b_state.owner() != maker.address() {
-                return Err(ProgramError::IllegalOwner);
-            }
+            check_token_account(maker_ata_b, mint_b, maker.address())?;
         }
     }
 
@@ -114,10 +111,17 @@ pub fn process_take_instruction_v2(accounts: &[AccountView], _data: &[u8]) -> Pr
             return Err(ProgramError::InvalidAccountData);
         }
 
-        let escrow_ata_state = TokenAccount::from_account_view(&escrow_ata)?;
-        if escrow_ata_state.owner() != escrow_account.address() {
-            return Err(ProgramError::IllegalOwner);
+        if escrow_state.is_expired(Clock::get()?.unix_timestamp) {
+            return Err(EscrowError::Expired.into());
+        }
+        if !escrow_state.can_be_taken_by(taker.address()) {
+            return Err(EscrowError::InvalidTaker.into());
         }
+        if let Some(expected_offer) = &expected_offer {
+            expected_offer.check(&escrow_state)?;
+        }
+
+        check_token_account(escrow_ata, mint_a, escrow_account.address())?;
 
         let amount_to_give = escrow_state.amount_to_give();
         let amount_to_receive = escrow_state.amount_to_receive();
@@ -132,11 +136,17 @@ pub fn process_take_instruction_v2(accounts: &[AccountView], _data: &[u8]) -> Pr
         )
     };
 
-    pinocchio_token::instructions::Transfer {
+    // The taker covers any transfer fee on mint B, so the maker receives the
+    // full amount.
+    TransferChecked {
         from: taker_ata_b,
+        mint: mint_b,
         to: maker_ata_b,
         authority: taker,
-        amount: amount_to_receive,
+        amount: amount_with_transfer_fee(mint_b, amount_to_receive)?,
+        decimals: mint_b_decimals,
+        token_program: token_program_b,
+        extra_accounts: remaining_accounts,
     }
     .invoke()?;
 
@@ -148,18 +158,24 @@ pub fn process_take_instruction_v2(accounts: &[AccountView], _data: &[u8]) -> Pr
     ];
     let signer = Signer::from(&vault_seed);
 
-    pinocchio_token::instructions::Transfer {
+    TransferChecked {
         from: escrow_ata,
+        mint: mint_a,
         to: taker_ata_a,
         authority: escrow_account,
         amount: amount_to_give,
+        decimals: mint_a_decimals,
+        token_program: token_program_a,
+        extra_accounts: remaining_accounts,
     }
     .invoke_signed(&[signer.clone()])?;
 
-    pinocchio_token::instructions::CloseAccount {
+    CloseAccount {
         account: escrow_ata,
+        mint: mint_a,
         destination: maker,
         authority: escrow_account,
+        token_program: token_program_a,
     }
     .invoke_signed(&[signer])?;
 
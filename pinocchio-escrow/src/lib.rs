@@ -5,9 +5,12 @@ use pinocchio::{
 
 use crate::instructions::EscrowInstructions;
 
+mod errors;
 mod instructions;
+mod native;
 mod state;
 mod tests;
+mod token;
 
 entrypoint!(process_instruction);
 
@@ -30,7 +33,12 @@ pub fn process_instruction(
         EscrowInstructions::Cancel => instructions::process_cancel_instruction(accounts, data),
         EscrowInstructions::MakeV2 => instructions::process_make_instruction_v2(accounts, data),
         EscrowInstructions::TakeV2 => instructions::process_take_instruction_v2(accounts, data),
-        EscrowInstructions::CancelV2 => instructions::process_cancel_instruction_v2(accounts, data)
+        EscrowInstructions::CancelV2 => instructions::process_cancel_instruction_v2(accounts, data),
+        EscrowInstructions::MakeBatch => instructions::process_make_batch_instruction(accounts, data),
+        EscrowInstructions::TakePartial => instructions::process_take_partial_instruction(accounts, data),
+        EscrowInstructions::Reclaim => instructions::process_reclaim_instruction(accounts, data),
+        EscrowInstructions::Amend => instructions::process_amend_instruction(accounts, data),
+        EscrowInstructions::Migrate => instructions::process_migrate_instruction(accounts, data)
         //        _ => return Err(ProgramError::InvalidInstructionData),
     }
 }
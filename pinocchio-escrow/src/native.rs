@@ -0,0 +1,44 @@
+use pinocchio::{error::ProgramError, AccountView, Address, ProgramResult};
+
+use crate::errors::EscrowError;
+
+// A native SOL leg is an offer whose mint is the system program. Its amounts
+// are lamports: a SOL deposit sits in the escrow PDA itself, on top of the
+// rent, and a SOL payment goes straight to the maker's wallet. Token account
+// slots of that leg are not read, so clients can pass the wallet or the
+// system program there.
+//
+// Make, Take, Cancel, Reclaim and Amend take native legs, as does MakeBatch
+// for mint B. MakeV2, TakeV2, TakePartial, CancelV2 and MakeBatch's mint A are
+// token-only.
+pub const NATIVE_MINT: Address = pinocchio_system::ID;
+
+pub fn is_native(mint: &AccountView) -> bool {
+    mint.address() == &NATIVE_MINT
+}
+
+// Called by the token-only instructions before reading their mints, so a SOL
+// leg fails with `NativeNotSupported` rather than as a mint of the wrong
+// owner.
+pub fn reject_native(mints: &[&AccountView]) -> ProgramResult {
+    if mints.iter().any(|mint| is_native(mint)) {
+        return Err(EscrowError::NativeNotSupported.into());
+    }
+    Ok(())
+}
+
+// Moves lamports out of an account this program owns, like the escrow PDA.
+pub fn transfer_lamports(from: &AccountView, to: &AccountView, lamports: u64) -> ProgramResult {
+    from.set_lamports(
+        from.lamports()
+            .checked_sub(lamports)
+            .ok_or(ProgramError::InsufficientFunds)?,
+    );
+    to.set_lamports(
+        to.lamports()
+            .checked_add(lamports)
+            .ok_or(ProgramError::ArithmeticOverflow)?,
+    );
+
+    Ok(())
+}
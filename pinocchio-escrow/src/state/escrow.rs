@@ -2,27 +2,54 @@ use pinocchio::{error::ProgramError, AccountView};
 
 use wincode::{SchemaRead, SchemaWrite};
 
+// `version` leads the account so layout changes can be told apart from the
+// first byte. Version 1 added `allowed_taker` to the unversioned layouts,
+// which `LegacyEscrow` still reads for `Migrate`.
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Default, PartialEq, SchemaWrite, SchemaRead)]
 pub struct Escrow {
+    pub version: u8,
     maker: [u8; 32],
     mint_a: [u8; 32],
     mint_b: [u8; 32],
     amount_to_receive: [u8; 8],
     amount_to_give: [u8; 8],
     seed: [u8; 8],
+    expires_at: [u8; 8],
+    allowed_taker: [u8; 32],
     pub bump: u8,
 }
 
 impl Escrow {
-    pub const LEN: usize = 32 + 32 + 32 + 8 + 8 + 8 + 1;
+    pub const VERSION: u8 = 1;
+    pub const LEN: usize = 1 + 32 + 32 + 32 + 8 + 8 + 8 + 8 + 32 + 1;
 
     pub fn from_account_info_wincode(account_info: &AccountView) -> Result<Self, ProgramError> {
         let data = account_info.try_borrow()?;
-        wincode::deserialize(&data).map_err(|_| ProgramError::InvalidAccountData)
+        let escrow: Self =
+            wincode::deserialize(&data).map_err(|_| ProgramError::InvalidAccountData)?;
+        if escrow.version != Self::VERSION {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(escrow)
     }
 
     pub fn from_account_info(account_info: &AccountView) -> Result<&mut Self, ProgramError> {
+        let escrow = Self::from_account_info_unchecked(account_info)?;
+        if escrow.version != Self::VERSION {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(escrow)
+    }
+
+    // For freshly created accounts, which are still zeroed.
+    pub fn init(account_info: &AccountView) -> Result<&mut Self, ProgramError> {
+        let escrow = Self::from_account_info_unchecked(account_info)?;
+        escrow.version = Self::VERSION;
+        Ok(escrow)
+    }
+
+    fn from_account_info_unchecked(account_info: &AccountView) -> Result<&mut Self, ProgramError> {
         let mut data = account_info.try_borrow_mut()?;
         if data.len() != Escrow::LEN {
             return Err(ProgramError::InvalidAccountData);
@@ -82,4 +109,77 @@ impl Escrow {
     pub fn set_seed(&mut self, seed: u64) {
         self.seed = seed.to_le_bytes();
     }
+
+    // Unix timestamp after which the offer can no longer be taken and anyone
+    // can `Reclaim` it. `0` means the offer never expires.
+    pub fn expires_at(&self) -> i64 {
+        i64::from_le_bytes(self.expires_at)
+    }
+
+    pub fn set_expires_at(&mut self, expires_at: i64) {
+        self.expires_at = expires_at.to_le_bytes();
+    }
+
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires_at() != 0 && now >= self.expires_at()
+    }
+
+    // All zeroes means anyone may take the offer.
+    pub fn allowed_taker(&self) -> Option<pinocchio::Address> {
+        (self.allowed_taker != [0; 32]).then(|| pinocchio::Address::from(self.allowed_taker))
+    }
+
+    pub fn set_allowed_taker(&mut self, allowed_taker: Option<&pinocchio::Address>) {
+        self.allowed_taker = allowed_taker.map_or([0; 32], |taker| *taker.as_array());
+    }
+
+    pub fn can_be_taken_by(&self, taker: &pinocchio::Address) -> bool {
+        self.allowed_taker().is_none_or(|allowed_taker| allowed_taker == *taker)
+    }
+}
+
+// An escrow written before the account was versioned. These layouts have no
+// version byte, so they are told apart by their length; `Migrate` rewrites
+// them into the current `Escrow`.
+pub struct LegacyEscrow {
+    pub maker: pinocchio::Address,
+    pub mint_a: pinocchio::Address,
+    pub mint_b: pinocchio::Address,
+    pub amount_to_receive: u64,
+    pub amount_to_give: u64,
+    pub seed: u64,
+    pub expires_at: i64,
+    pub bump: u8,
+}
+
+impl LegacyEscrow {
+    // maker, mint_a, mint_b, amount_to_receive, amount_to_give, seed, bump.
+    pub const ORIGINAL_LEN: usize = 32 + 32 + 32 + 8 + 8 + 8 + 1;
+    // The same with `expires_at` before `bump`.
+    pub const EXPIRY_LEN: usize = Self::ORIGINAL_LEN + 8;
+
+    pub fn read(data: &[u8]) -> Result<Self, ProgramError> {
+        let (bump, expires_at) = match data.len() {
+            Self::ORIGINAL_LEN => (data[120], 0),
+            Self::EXPIRY_LEN => (data[128], i64::from_le_bytes(read_array(data, 120))),
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        Ok(Self {
+            maker: pinocchio::Address::from(read_array::<32>(data, 0)),
+            mint_a: pinocchio::Address::from(read_array::<32>(data, 32)),
+            mint_b: pinocchio::Address::from(read_array::<32>(data, 64)),
+            amount_to_receive: u64::from_le_bytes(read_array(data, 96)),
+            amount_to_give: u64::from_le_bytes(read_array(data, 104)),
+            seed: u64::from_le_bytes(read_array(data, 112)),
+            expires_at,
+            bump,
+        })
+    }
+}
+
+fn read_array<const N: usize>(data: &[u8], offset: usize) -> [u8; N] {
+    let mut bytes = [0; N];
+    bytes.copy_from_slice(&data[offset..offset + N]);
+    bytes
 }
@@ -4,10 +4,18 @@ use {
         LiteSVM, types::{FailedTransactionMetadata, TransactionMetadata}
     }, litesvm_token::{
         CreateAssociatedTokenAccount, CreateMint, MintTo, spl_token::ID as TOKEN_PROGRAM_ID
-    }, pinocchio_token::state::TokenAccount, solana_instruction::Instruction, solana_keypair::Keypair, solana_message::{AccountMeta, Message}, solana_native_token::LAMPORTS_PER_SOL, solana_pubkey::Pubkey, solana_sdk_ids::system_program::ID as SYSTEM_PROGRAM_ID, solana_signer::Signer, solana_transaction::Transaction, spl_associated_token_account::ID as ASSOCIATED_TOKEN_PROGRAM_ID, std::path::PathBuf, wincode::{SchemaRead, SchemaWrite}
+    }, pinocchio_token::state::TokenAccount, solana_account::Account, solana_clock::Clock, solana_instruction::Instruction, solana_keypair::Keypair, solana_message::{AccountMeta, Message}, solana_native_token::LAMPORTS_PER_SOL, solana_pubkey::Pubkey, solana_sdk_ids::system_program::ID as SYSTEM_PROGRAM_ID, solana_signer::Signer, solana_transaction::Transaction, spl_associated_token_account::ID as ASSOCIATED_TOKEN_PROGRAM_ID, spl_token_2022::{ID as TOKEN_2022_PROGRAM_ID, extension::{ExtensionType, transfer_fee::instruction::initialize_transfer_fee_config, transfer_hook::instruction::initialize as initialize_transfer_hook}, instruction::initialize_mint2, state::Mint as Token2022Mint}, std::path::PathBuf, wincode::{SchemaRead, SchemaWrite}
 };
 
 const PROGRAM_ID: Pubkey = crate::ID;
+// The program takes the system program as the mint of a native SOL leg.
+const NATIVE_MINT: Pubkey = SYSTEM_PROGRAM_ID;
+// The SPL transfer hook example program, built as described in
+// tests/fixtures/README.md. With an empty extra account list it only checks
+// that Token-2022 called it mid-transfer.
+const TRANSFER_HOOK_SO: &str = "tests/fixtures/spl_transfer_hook_example.so";
+// `spl-transfer-hook-interface:initialize-extra-account-metas`
+const INITIALIZE_EXTRA_ACCOUNT_METAS: [u8; 8] = [43, 34, 13, 49, 167, 88, 235, 235];
 
 fn send_tx(
     svm: &mut LiteSVM,
@@ -23,6 +31,137 @@ fn send_tx(
     tx
 }
 
+fn create_token_2022_mint(
+    svm: &mut LiteSVM,
+    authority: &Keypair,
+    transfer_fee_bps: Option<u16>,
+    transfer_hook_program: Option<Pubkey>,
+) -> Pubkey {
+    let mint = Keypair::new();
+    let mut extensions = vec![];
+    if transfer_fee_bps.is_some() {
+        extensions.push(ExtensionType::TransferFeeConfig);
+    }
+    if transfer_hook_program.is_some() {
+        extensions.push(ExtensionType::TransferHook);
+    }
+    let space = ExtensionType::try_calculate_account_len::<Token2022Mint>(&extensions).unwrap();
+    let lamports = svm.minimum_balance_for_rent_exemption(space);
+
+    // System program `CreateAccount`: u32 tag, lamports, space, owner.
+    let create_account_ix = Instruction {
+        program_id: SYSTEM_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(authority.pubkey(), true),
+            AccountMeta::new(mint.pubkey(), true),
+        ],
+        data: [
+            0u32.to_le_bytes().to_vec(),
+            lamports.to_le_bytes().to_vec(),
+            (space as u64).to_le_bytes().to_vec(),
+            TOKEN_2022_PROGRAM_ID.to_bytes().to_vec(),
+        ]
+        .concat(),
+    };
+
+    let mut ixs = vec![create_account_ix];
+    if let Some(transfer_fee_bps) = transfer_fee_bps {
+        ixs.push(
+            initialize_transfer_fee_config(
+                &TOKEN_2022_PROGRAM_ID,
+                &mint.pubkey(),
+                Some(&authority.pubkey()),
+                Some(&authority.pubkey()),
+                transfer_fee_bps,
+                u64::MAX,
+            )
+            .unwrap(),
+        );
+    }
+    if let Some(transfer_hook_program) = transfer_hook_program {
+        ixs.push(
+            initialize_transfer_hook(
+                &TOKEN_2022_PROGRAM_ID,
+                &mint.pubkey(),
+                Some(authority.pubkey()),
+                Some(transfer_hook_program),
+            )
+            .unwrap(),
+        );
+    }
+    ixs.push(
+        initialize_mint2(
+            &TOKEN_2022_PROGRAM_ID,
+            &mint.pubkey(),
+            &authority.pubkey(),
+            None,
+            6,
+        )
+        .unwrap(),
+    );
+
+    send_tx(svm, &ixs, authority, &[authority, &mint]).unwrap();
+
+    mint.pubkey()
+}
+
+// Creates the hook's validation account for `mint` with no extra accounts and
+// returns its address. The example program only allocates it, so it is
+// funded first.
+fn initialize_extra_account_metas(
+    svm: &mut LiteSVM,
+    authority: &Keypair,
+    transfer_hook_program: &Pubkey,
+    mint: &Pubkey,
+) -> Pubkey {
+    let validation = Pubkey::find_program_address(
+        &[b"extra-account-metas", mint.as_ref()],
+        transfer_hook_program,
+    )
+    .0;
+    // TLV discriminator and length, then an empty list.
+    let space = 8 + 4 + 4;
+
+    // System program `Transfer`: u32 tag, lamports.
+    let fund_ix = Instruction {
+        program_id: SYSTEM_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(authority.pubkey(), true),
+            AccountMeta::new(validation, false),
+        ],
+        data: [
+            2u32.to_le_bytes().to_vec(),
+            svm.minimum_balance_for_rent_exemption(space).to_le_bytes().to_vec(),
+        ]
+        .concat(),
+    };
+
+    let initialize_ix = Instruction {
+        program_id: *transfer_hook_program,
+        accounts: vec![
+            AccountMeta::new(validation, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(authority.pubkey(), true),
+            AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+        ],
+        data: [INITIALIZE_EXTRA_ACCOUNT_METAS.to_vec(), 0u32.to_le_bytes().to_vec()].concat(),
+    };
+
+    send_tx(svm, &[fund_ix, initialize_ix], authority, &[authority]).unwrap();
+
+    validation
+}
+
+// `ExpectedOffer` of the take instructions, from `(amount_to_give,
+// amount_to_receive)`; empty when the taker does not pin the offer.
+fn expected_offer_data(expected_offer: Option<(u64, u64)>) -> Vec<u8> {
+    expected_offer
+        .map(|(amount_to_give, amount_to_receive)| {
+            [amount_to_receive.to_le_bytes(), amount_to_give.to_le_bytes()].concat()
+        })
+        .unwrap_or_default()
+}
+
 pub struct EscrowTestBuilder {
     svm: LiteSVM,
     maker: Keypair,
@@ -35,6 +174,10 @@ pub struct EscrowTestBuilder {
     taker_ata_b: Option<Pubkey>,
     escrow: Option<(Pubkey, u8)>,
     escrow_ata: Option<Pubkey>,
+    token_program_a: Pubkey,
+    token_program_b: Pubkey,
+    transfer_hook_program: Option<Pubkey>,
+    extra_accounts: Vec<AccountMeta>,
     last_tx: Option<TransactionMetadata>,
     last_tx_error: Option<String>,
 }
@@ -66,6 +209,10 @@ impl EscrowTestBuilder {
             taker_ata_b: None,
             escrow: None,
             escrow_ata: None,
+            token_program_a: TOKEN_PROGRAM_ID,
+            token_program_b: TOKEN_PROGRAM_ID,
+            transfer_hook_program: None,
+            extra_accounts: vec![],
             last_tx: None,
             last_tx_error: None,
         }
@@ -92,10 +239,117 @@ impl EscrowTestBuilder {
         self
     }
 
+    // Replaces mint A with a Token-2022 mint, optionally carrying a transfer
+    // fee of `transfer_fee_bps` with no maximum. Call before creating ATAs.
+    pub fn create_token_2022_mint_a(mut self, transfer_fee_bps: Option<u16>) -> Self {
+        let mint_a = create_token_2022_mint(&mut self.svm, &self.maker, transfer_fee_bps, None);
+        println!("Mint A (Token-2022): {}", mint_a);
+
+        self.mint_a = Some(mint_a);
+        self.token_program_a = TOKEN_2022_PROGRAM_ID;
+
+        self
+    }
+
+    pub fn create_token_2022_mint_b(mut self, transfer_fee_bps: Option<u16>) -> Self {
+        let mint_b = create_token_2022_mint(&mut self.svm, &self.maker, transfer_fee_bps, None);
+        println!("Mint B (Token-2022): {}", mint_b);
+
+        self.mint_b = Some(mint_b);
+        self.token_program_b = TOKEN_2022_PROGRAM_ID;
+
+        self
+    }
+
+    // Replaces mint A with a Token-2022 mint whose transfers call the example
+    // transfer hook. The hook program and the mint's validation account are
+    // added to the extra accounts of every Make, Take, Cancel, Amend and
+    // Reclaim, so with both mints hooked, both legs of a Take share one list.
+    pub fn create_transfer_hook_mint_a(mut self) -> Self {
+        let transfer_hook_program = self.load_transfer_hook_program();
+        let mint_a =
+            create_token_2022_mint(&mut self.svm, &self.maker, None, Some(transfer_hook_program));
+        let validation =
+            initialize_extra_account_metas(&mut self.svm, &self.maker, &transfer_hook_program, &mint_a);
+        println!("Mint A (transfer hook): {}", mint_a);
+
+        self.mint_a = Some(mint_a);
+        self.token_program_a = TOKEN_2022_PROGRAM_ID;
+        self.extra_accounts.push(AccountMeta::new_readonly(validation, false));
+
+        self
+    }
+
+    pub fn create_transfer_hook_mint_b(mut self) -> Self {
+        let transfer_hook_program = self.load_transfer_hook_program();
+        let mint_b =
+            create_token_2022_mint(&mut self.svm, &self.maker, None, Some(transfer_hook_program));
+        let validation =
+            initialize_extra_account_metas(&mut self.svm, &self.maker, &transfer_hook_program, &mint_b);
+        println!("Mint B (transfer hook): {}", mint_b);
+
+        self.mint_b = Some(mint_b);
+        self.token_program_b = TOKEN_2022_PROGRAM_ID;
+        self.extra_accounts.push(AccountMeta::new_readonly(validation, false));
+
+        self
+    }
+
+    // The hook program is a build output that is not checked in. Hook tests
+    // return early when it is missing rather than fail on a clean checkout.
+    pub fn transfer_hook_program_missing() -> bool {
+        let so_path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(TRANSFER_HOOK_SO);
+        if so_path.exists() {
+            return false;
+        }
+
+        eprintln!(
+            "skipping transfer hook test: {} not found, see tests/fixtures/README.md",
+            so_path.display()
+        );
+        true
+    }
+
+    fn load_transfer_hook_program(&mut self) -> Pubkey {
+        if let Some(transfer_hook_program) = self.transfer_hook_program {
+            return transfer_hook_program;
+        }
+
+        let transfer_hook_program = Pubkey::new_unique();
+        let so_path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(TRANSFER_HOOK_SO);
+        let program_data = std::fs::read(so_path).expect("Failed to read transfer hook SO file");
+        self.svm
+            .add_program(transfer_hook_program, &program_data)
+            .expect("Failed to add transfer hook program");
+
+        self.transfer_hook_program = Some(transfer_hook_program);
+        self.extra_accounts
+            .push(AccountMeta::new_readonly(transfer_hook_program, false));
+
+        transfer_hook_program
+    }
+
+    // Makes mint A native SOL. Its token account slots get the system
+    // program, so there are no maker ATA A or escrow ATA to create.
+    pub fn use_native_mint_a(mut self) -> Self {
+        self.mint_a = Some(NATIVE_MINT);
+        self.maker_ata_a = Some(NATIVE_MINT);
+
+        self
+    }
+
+    pub fn use_native_mint_b(mut self) -> Self {
+        self.mint_b = Some(NATIVE_MINT);
+        self.maker_ata_b = Some(NATIVE_MINT);
+
+        self
+    }
+
     pub fn create_maker_ata_a(mut self) -> Self {
         let mint_a = self.mint_a.expect("Mint A not created");
         let maker_ata_a = CreateAssociatedTokenAccount::new(&mut self.svm, &self.maker, &mint_a)
             .owner(&self.maker.pubkey())
+            .token_program_id(&self.token_program_a)
             .send()
             .unwrap();
         println!("Maker ATA A: {}\n", maker_ata_a);
@@ -109,6 +363,7 @@ impl EscrowTestBuilder {
         let mint_b = self.mint_b.expect("Mint B not created");
         let maker_ata_b = CreateAssociatedTokenAccount::new(&mut self.svm, &self.maker, &mint_b)
             .owner(&self.maker.pubkey())
+            .token_program_id(&self.token_program_b)
             .send()
             .unwrap();
 
@@ -125,6 +380,7 @@ impl EscrowTestBuilder {
             &self.maker_ata_a.unwrap(),
             amount,
         )
+        .token_program_id(&self.token_program_a)
         .send()
         .unwrap();
 
@@ -139,6 +395,7 @@ impl EscrowTestBuilder {
             &self.taker_ata_b.unwrap(),
             amount,
         )
+        .token_program_id(&self.token_program_b)
         .send()
         .unwrap();
 
@@ -156,20 +413,41 @@ impl EscrowTestBuilder {
         self
     }
 
+    // Swaps in a taker set up earlier, e.g. after another one was tried.
+    pub fn set_taker(mut self, taker: Keypair) -> Self {
+        self.taker = Some(taker);
+
+        self
+    }
+
+    pub fn taker_keypair(&self) -> Keypair {
+        self.taker.as_ref().expect("Taker not created").insecure_clone()
+    }
+
     pub fn create_taker_atas(mut self) -> Self {
         let taker = self.taker.as_ref().expect("Taker not created");
         let mint_a = self.mint_a.expect("Mint A not created");
         let mint_b = self.mint_b.expect("Mint B not created");
 
-        let taker_ata_a = CreateAssociatedTokenAccount::new(&mut self.svm, taker, &mint_a)
-            .owner(&taker.pubkey())
-            .send()
-            .unwrap();
+        let taker_ata_a = if mint_a == NATIVE_MINT {
+            NATIVE_MINT
+        } else {
+            CreateAssociatedTokenAccount::new(&mut self.svm, taker, &mint_a)
+                .owner(&taker.pubkey())
+                .token_program_id(&self.token_program_a)
+                .send()
+                .unwrap()
+        };
 
-        let taker_ata_b = CreateAssociatedTokenAccount::new(&mut self.svm, taker, &mint_b)
-            .owner(&taker.pubkey())
-            .send()
-            .unwrap();
+        let taker_ata_b = if mint_b == NATIVE_MINT {
+            NATIVE_MINT
+        } else {
+            CreateAssociatedTokenAccount::new(&mut self.svm, taker, &mint_b)
+                .owner(&taker.pubkey())
+                .token_program_id(&self.token_program_b)
+                .send()
+                .unwrap()
+        };
 
         self.taker_ata_a = Some(taker_ata_a);
         self.taker_ata_b = Some(taker_ata_b);
@@ -188,10 +466,15 @@ impl EscrowTestBuilder {
         );
         println!("Escrow PDA: {}\n", escrow.0);
 
-        let escrow_ata = spl_associated_token_account::get_associated_token_address(
-            &escrow.0,
-            &self.mint_a.unwrap(),
-        );
+        let escrow_ata = if self.mint_a == Some(NATIVE_MINT) {
+            NATIVE_MINT
+        } else {
+            spl_associated_token_account::get_associated_token_address_with_program_id(
+                &escrow.0,
+                &self.mint_a.unwrap(),
+                &self.token_program_a,
+            )
+        };
         println!("Escrow ATA: {}\n", escrow_ata);
 
         self.escrow = Some(escrow);
@@ -200,7 +483,51 @@ impl EscrowTestBuilder {
         self
     }
 
-    pub fn execute_make(mut self, amount_to_give: u64, seed: u64, amount_to_receive: u64) -> Self {
+    pub fn execute_make(self, amount_to_give: u64, seed: u64, amount_to_receive: u64) -> Self {
+        self.execute_make_with_expiry(amount_to_give, seed, amount_to_receive, None)
+    }
+
+    // `None` leaves the optional `expires_at` field out of the instruction
+    // data entirely, as clients predating it do.
+    pub fn execute_make_with_expiry(
+        self,
+        amount_to_give: u64,
+        seed: u64,
+        amount_to_receive: u64,
+        expires_at: Option<i64>,
+    ) -> Self {
+        self.execute_make_with_options(amount_to_give, seed, amount_to_receive, expires_at, None)
+    }
+
+    // `allowed_taker` follows `expires_at` in the instruction data, so setting
+    // it also writes `expires_at`, as `0` (never) when not given.
+    pub fn execute_make_with_options(
+        self,
+        amount_to_give: u64,
+        seed: u64,
+        amount_to_receive: u64,
+        expires_at: Option<i64>,
+        allowed_taker: Option<Pubkey>,
+    ) -> Self {
+        let expires_at = expires_at.or(allowed_taker.map(|_| 0));
+        let tail = [
+            expires_at.map(|expires_at| expires_at.to_le_bytes().to_vec()).unwrap_or_default(),
+            allowed_taker.map(|taker| taker.to_bytes().to_vec()).unwrap_or_default(),
+        ]
+        .concat();
+
+        self.execute_make_with_raw_tail(amount_to_give, seed, amount_to_receive, &tail)
+    }
+
+    // `tail` is appended as-is after the fixed fields, so malformed optional
+    // fields can be sent.
+    pub fn execute_make_with_raw_tail(
+        mut self,
+        amount_to_give: u64,
+        seed: u64,
+        amount_to_receive: u64,
+        tail: &[u8],
+    ) -> Self {
         let bump: u8 = self.escrow_bump();
         println!("Bump: {}", bump);
 
@@ -210,11 +537,12 @@ impl EscrowTestBuilder {
             amount_to_receive.to_le_bytes().to_vec(),
             amount_to_give.to_le_bytes().to_vec(),
             seed.to_le_bytes().to_vec(),
+            tail.to_vec(),
         ]
         .concat();
 
         let associated_token_program = ASSOCIATED_TOKEN_PROGRAM_ID;
-        let token_program = TOKEN_PROGRAM_ID;
+        let token_program = self.token_program_a;
         let system_program = SYSTEM_PROGRAM_ID;
 
         let make_ix = Instruction {
@@ -229,18 +557,29 @@ impl EscrowTestBuilder {
                 AccountMeta::new_readonly(system_program, false),
                 AccountMeta::new_readonly(token_program, false),
                 AccountMeta::new_readonly(associated_token_program, false),
-            ],
+            ]
+            .into_iter()
+            .chain(self.extra_accounts.iter().cloned())
+            .collect(),
             data: make_data,
         };
 
-        let tx = send_tx(&mut self.svm, &[make_ix], &self.maker, &[&self.maker]).unwrap();
+        let tx = send_tx(&mut self.svm, &[make_ix], &self.maker, &[&self.maker]);
 
-        println!("\n\nMake transaction successful");
-        println!("CUs Consumed: {}", tx.compute_units_consumed);
-        println!("Tx Signature: {}", tx.signature);
+        match &tx {
+            Ok(tx_result) => {
+                println!("\n\nMake transaction successful");
+                println!("CUs Consumed: {}", tx_result.compute_units_consumed);
+                println!("Tx Signature: {}", tx_result.signature);
 
-        self.last_tx = Some(tx);
-        self.last_tx_error = None;
+                self.last_tx = Some(tx_result.clone());
+                self.last_tx_error = None;
+            }
+            Err(err) => {
+                self.last_tx = None;
+                self.last_tx_error = Some(format!("{:?}", err));
+            }
+        }
 
         self
     }
@@ -272,7 +611,7 @@ impl EscrowTestBuilder {
         let make_v2_data = [vec![EscrowInstructions::MakeV2 as u8], encoded].concat();
 
         let associated_token_program = ASSOCIATED_TOKEN_PROGRAM_ID;
-        let token_program = TOKEN_PROGRAM_ID;
+        let token_program = self.token_program_a;
         let system_program = SYSTEM_PROGRAM_ID;
 
         let make_v2_ix = Instruction {
@@ -303,13 +642,122 @@ impl EscrowTestBuilder {
         self
     }
 
-    pub fn execute_take(mut self) -> Self {
+    pub fn escrow_accounts_for(&self, seed: u64) -> (Pubkey, u8, Pubkey) {
+        let (escrow, bump) = Pubkey::find_program_address(
+            &[
+                b"escrow",
+                self.maker.pubkey().as_ref(),
+                &seed.to_le_bytes(),
+            ],
+            &PROGRAM_ID,
+        );
+        let escrow_ata = spl_associated_token_account::get_associated_token_address_with_program_id(
+            &escrow,
+            &self.mint_a.unwrap(),
+            &self.token_program_a,
+        );
+
+        (escrow, bump, escrow_ata)
+    }
+
+    // Each offer is `(amount_to_give, seed, amount_to_receive)`, all against
+    // the builder's mint B.
+    pub fn execute_make_batch(self, offers: &[(u64, u64, u64)]) -> Self {
+        let mint_b = self.mint_b.unwrap();
+        self.execute_make_batch_with_mint_b(offers, mint_b)
+    }
+
+    pub fn execute_make_batch_with_mint_b(mut self, offers: &[(u64, u64, u64)], mint_b: Pubkey) -> Self {
+        #[derive(SchemaWrite, SchemaRead)]
+        pub struct MakeBatchEntry {
+            pub bump: u8,
+            pub seed: u64,
+            pub amount_to_give: u64,
+            pub amount_to_receive: u64,
+            pub mint_b: [u8; 32],
+            pub expires_at: i64,
+            pub allowed_taker: [u8; 32],
+        }
+
+        let associated_token_program = ASSOCIATED_TOKEN_PROGRAM_ID;
+        let token_program = self.token_program_a;
+        let system_program = SYSTEM_PROGRAM_ID;
+
+        let mut make_batch_data = vec![EscrowInstructions::MakeBatch as u8];
+        let mut accounts = vec![
+            AccountMeta::new(self.maker.pubkey(), true),
+            AccountMeta::new_readonly(self.mint_a.unwrap(), false),
+            AccountMeta::new(self.maker_ata_a.unwrap(), false),
+            AccountMeta::new_readonly(system_program, false),
+            AccountMeta::new_readonly(token_program, false),
+            AccountMeta::new_readonly(associated_token_program, false),
+        ];
+
+        for &(amount_to_give, seed, amount_to_receive) in offers {
+            let (escrow, bump, escrow_ata) = self.escrow_accounts_for(seed);
+
+            let entry = MakeBatchEntry {
+                bump,
+                seed,
+                amount_to_give,
+                amount_to_receive,
+                mint_b: mint_b.to_bytes(),
+                expires_at: 0,
+                allowed_taker: [0; 32],
+            };
+            make_batch_data.extend(wincode::serialize(&entry).unwrap());
+
+            accounts.push(AccountMeta::new(escrow, false));
+            accounts.push(AccountMeta::new(escrow_ata, false));
+            accounts.push(AccountMeta::new_readonly(mint_b, false));
+        }
+
+        let make_batch_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts,
+            data: make_batch_data,
+        };
+
+        let tx = send_tx(&mut self.svm, &[make_batch_ix], &self.maker, &[&self.maker]);
+
+        match &tx {
+            Ok(tx_result) => {
+                println!("\n\nMakeBatch transaction successful");
+                println!("CUs Consumed: {}", tx_result.compute_units_consumed);
+                println!("Tx Signature: {}", tx_result.signature);
+
+                self.last_tx = Some(tx_result.clone());
+                self.last_tx_error = None;
+            }
+            Err(err) => {
+                self.last_tx = None;
+                self.last_tx_error = Some(format!("{:?}", err));
+            }
+        }
+
+        self
+    }
+
+    pub fn execute_take(self) -> Self {
+        self.send_take(None)
+    }
+
+    // Sends the offer the taker expects, so the take fails if it was amended.
+    pub fn execute_take_expecting(self, amount_to_give: u64, amount_to_receive: u64) -> Self {
+        self.send_take(Some((amount_to_give, amount_to_receive)))
+    }
+
+    fn send_take(mut self, expected_offer: Option<(u64, u64)>) -> Self {
         let taker = self.taker.as_ref().expect("Taker not created");
 
-        let take_data = [vec![EscrowInstructions::Take as u8]].concat();
+        let take_data = [
+            vec![EscrowInstructions::Take as u8],
+            expected_offer_data(expected_offer),
+        ]
+        .concat();
 
         let associated_token_program = ASSOCIATED_TOKEN_PROGRAM_ID;
-        let token_program = TOKEN_PROGRAM_ID;
+        let token_program = self.token_program_a;
         let system_program = SYSTEM_PROGRAM_ID;
 
         let take_ix = Instruction {
@@ -317,7 +765,7 @@ impl EscrowTestBuilder {
             accounts: vec![
                 AccountMeta::new(taker.pubkey(), true),
                 AccountMeta::new(self.maker.pubkey(), false),
-                AccountMeta::new_readonly(self.mint_a.unwrap(), false),
+                AccountMeta::new(self.mint_a.unwrap(), false),
                 AccountMeta::new_readonly(self.mint_b.unwrap(), false),
                 AccountMeta::new(self.taker_ata_a.unwrap(), false),
                 AccountMeta::new(self.taker_ata_b.unwrap(), false),
@@ -326,8 +774,12 @@ impl EscrowTestBuilder {
                 AccountMeta::new(self.escrow_ata.unwrap(), false),
                 AccountMeta::new_readonly(system_program, false),
                 AccountMeta::new_readonly(token_program, false),
+                AccountMeta::new_readonly(self.token_program_b, false),
                 AccountMeta::new_readonly(associated_token_program, false),
-            ],
+            ]
+            .into_iter()
+            .chain(self.extra_accounts.iter().cloned())
+            .collect(),
             data: take_data,
         };
 
@@ -352,13 +804,81 @@ impl EscrowTestBuilder {
         self
     }
 
+    pub fn execute_take_partial(self, amount_b: u64) -> Self {
+        self.send_take_partial(amount_b, None)
+    }
+
+    pub fn execute_take_partial_expecting(
+        self,
+        amount_b: u64,
+        amount_to_give: u64,
+        amount_to_receive: u64,
+    ) -> Self {
+        self.send_take_partial(amount_b, Some((amount_to_give, amount_to_receive)))
+    }
+
+    fn send_take_partial(mut self, amount_b: u64, expected_offer: Option<(u64, u64)>) -> Self {
+        let taker = self.taker.as_ref().expect("Taker not created");
+
+        let take_partial_data = [
+            vec![EscrowInstructions::TakePartial as u8],
+            amount_b.to_le_bytes().to_vec(),
+            expected_offer_data(expected_offer),
+        ]
+        .concat();
+
+        let associated_token_program = ASSOCIATED_TOKEN_PROGRAM_ID;
+        let token_program = self.token_program_a;
+        let system_program = SYSTEM_PROGRAM_ID;
+
+        let take_partial_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(taker.pubkey(), true),
+                AccountMeta::new(self.maker.pubkey(), false),
+                AccountMeta::new(self.mint_a.unwrap(), false),
+                AccountMeta::new_readonly(self.mint_b.unwrap(), false),
+                AccountMeta::new(self.taker_ata_a.unwrap(), false),
+                AccountMeta::new(self.taker_ata_b.unwrap(), false),
+                AccountMeta::new(self.maker_ata_b.unwrap(), false),
+                AccountMeta::new(self.escrow.unwrap().0, false),
+                AccountMeta::new(self.escrow_ata.unwrap(), false),
+                AccountMeta::new_readonly(system_program, false),
+                AccountMeta::new_readonly(token_program, false),
+                AccountMeta::new_readonly(self.token_program_b, false),
+                AccountMeta::new_readonly(associated_token_program, false),
+            ],
+            data: take_partial_data,
+        };
+
+        let tx = send_tx(&mut self.svm, &[take_partial_ix], &taker, &[&taker]);
+
+        match &tx {
+            Ok(tx_result) => {
+                println!("\n\nTakePartial transaction successful");
+                println!("CUs Consumed: {}", tx_result.compute_units_consumed);
+                println!("Tx Signature: {}", tx_result.signature);
+
+                self.last_tx = Some(tx_result.clone());
+                self.last_tx_error = None;
+            }
+            Err(err) => {
+                print!("Error: {:?}", err);
+                self.last_tx = None;
+                self.last_tx_error = Some(format!("{:?}", err));
+            }
+        }
+
+        self
+    }
+
     pub fn execute_take_v2(mut self) -> Self {
         let taker = self.taker.as_ref().expect("Taker not created");
 
         let take_v2_data = [vec![EscrowInstructions::TakeV2 as u8]].concat();
 
         let associated_token_program = ASSOCIATED_TOKEN_PROGRAM_ID;
-        let token_program = TOKEN_PROGRAM_ID;
+        let token_program = self.token_program_a;
         let system_program = SYSTEM_PROGRAM_ID;
 
         let take_v2_ix = Instruction {
@@ -366,7 +886,7 @@ impl EscrowTestBuilder {
             accounts: vec![
                 AccountMeta::new(taker.pubkey(), true),
                 AccountMeta::new(self.maker.pubkey(), false),
-                AccountMeta::new_readonly(self.mint_a.unwrap(), false),
+                AccountMeta::new(self.mint_a.unwrap(), false),
                 AccountMeta::new_readonly(self.mint_b.unwrap(), false),
                 AccountMeta::new(self.taker_ata_a.unwrap(), false),
                 AccountMeta::new(self.taker_ata_b.unwrap(), false),
@@ -375,6 +895,7 @@ impl EscrowTestBuilder {
                 AccountMeta::new(self.escrow_ata.unwrap(), false),
                 AccountMeta::new_readonly(system_program, false),
                 AccountMeta::new_readonly(token_program, false),
+                AccountMeta::new_readonly(self.token_program_b, false),
                 AccountMeta::new_readonly(associated_token_program, false),
             ],
             data: take_v2_data,
@@ -405,21 +926,24 @@ impl EscrowTestBuilder {
         let cancel_data = [vec![EscrowInstructions::Cancel as u8]].concat();
 
         let associated_token_program = ASSOCIATED_TOKEN_PROGRAM_ID;
-        let token_program = TOKEN_PROGRAM_ID;
+        let token_program = self.token_program_a;
         let system_program = SYSTEM_PROGRAM_ID;
 
         let cancel_ix = Instruction {
             program_id: PROGRAM_ID,
             accounts: vec![
                 AccountMeta::new(self.maker.pubkey(), true),
-                AccountMeta::new_readonly(self.mint_a.unwrap(), false),
+                AccountMeta::new(self.mint_a.unwrap(), false),
                 AccountMeta::new(self.maker_ata_a.unwrap(), false),
                 AccountMeta::new(self.escrow.unwrap().0, false),
                 AccountMeta::new(self.escrow_ata.unwrap(), false),
                 AccountMeta::new_readonly(system_program, false),
                 AccountMeta::new_readonly(token_program, false),
                 AccountMeta::new_readonly(associated_token_program, false),
-            ],
+            ]
+            .into_iter()
+            .chain(self.extra_accounts.iter().cloned())
+            .collect(),
             data: cancel_data,
         };
 
@@ -447,14 +971,14 @@ impl EscrowTestBuilder {
         let cancel_v2_data = [vec![EscrowInstructions::CancelV2 as u8]].concat();
 
         let associated_token_program = ASSOCIATED_TOKEN_PROGRAM_ID;
-        let token_program = TOKEN_PROGRAM_ID;
+        let token_program = self.token_program_a;
         let system_program = SYSTEM_PROGRAM_ID;
 
         let cancel_v2_ix = Instruction {
             program_id: PROGRAM_ID,
             accounts: vec![
                 AccountMeta::new(self.maker.pubkey(), true),
-                AccountMeta::new_readonly(self.mint_a.unwrap(), false),
+                AccountMeta::new(self.mint_a.unwrap(), false),
                 AccountMeta::new(self.maker_ata_a.unwrap(), false),
                 AccountMeta::new(self.escrow.unwrap().0, false),
                 AccountMeta::new(self.escrow_ata.unwrap(), false),
@@ -485,6 +1009,227 @@ impl EscrowTestBuilder {
         self
     }
 
+    pub fn execute_amend(mut self, amount_to_give: u64, amount_to_receive: u64) -> Self {
+        let amend_data = [
+            vec![EscrowInstructions::Amend as u8],
+            amount_to_receive.to_le_bytes().to_vec(),
+            amount_to_give.to_le_bytes().to_vec(),
+        ]
+        .concat();
+
+        let associated_token_program = ASSOCIATED_TOKEN_PROGRAM_ID;
+        let token_program = self.token_program_a;
+        let system_program = SYSTEM_PROGRAM_ID;
+
+        let amend_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(self.maker.pubkey(), true),
+                AccountMeta::new_readonly(self.mint_a.unwrap(), false),
+                AccountMeta::new(self.maker_ata_a.unwrap(), false),
+                AccountMeta::new(self.escrow.unwrap().0, false),
+                AccountMeta::new(self.escrow_ata.unwrap(), false),
+                AccountMeta::new_readonly(system_program, false),
+                AccountMeta::new_readonly(token_program, false),
+                AccountMeta::new_readonly(associated_token_program, false),
+            ]
+            .into_iter()
+            .chain(self.extra_accounts.iter().cloned())
+            .collect(),
+            data: amend_data,
+        };
+
+        let tx = send_tx(&mut self.svm, &[amend_ix], &self.maker, &[&self.maker]);
+
+        match &tx {
+            Ok(tx_result) => {
+                println!("\n\nAmend transaction successful");
+                println!("CUs Consumed: {}", tx_result.compute_units_consumed);
+                println!("Tx Signature: {}", tx_result.signature);
+
+                self.last_tx = Some(tx_result.clone());
+                self.last_tx_error = None;
+            }
+            Err(err) => {
+                self.last_tx = None;
+                self.last_tx_error = Some(format!("{:?}", err));
+            }
+        }
+
+        self
+    }
+
+    pub fn execute_reclaim(mut self) -> Self {
+        let caller = Keypair::new();
+        self.svm
+            .airdrop(&caller.pubkey(), 5 * LAMPORTS_PER_SOL)
+            .expect("Failed to airdrop SOL to caller");
+
+        let reclaim_data = [vec![EscrowInstructions::Reclaim as u8]].concat();
+
+        let associated_token_program = ASSOCIATED_TOKEN_PROGRAM_ID;
+        let token_program = self.token_program_a;
+        let system_program = SYSTEM_PROGRAM_ID;
+
+        let reclaim_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(caller.pubkey(), true),
+                AccountMeta::new(self.maker.pubkey(), false),
+                AccountMeta::new(self.mint_a.unwrap(), false),
+                AccountMeta::new(self.maker_ata_a.unwrap(), false),
+                AccountMeta::new(self.escrow.unwrap().0, false),
+                AccountMeta::new(self.escrow_ata.unwrap(), false),
+                AccountMeta::new_readonly(system_program, false),
+                AccountMeta::new_readonly(token_program, false),
+                AccountMeta::new_readonly(associated_token_program, false),
+            ]
+            .into_iter()
+            .chain(self.extra_accounts.iter().cloned())
+            .collect(),
+            data: reclaim_data,
+        };
+
+        let tx = send_tx(&mut self.svm, &[reclaim_ix], &caller, &[&caller]);
+
+        match &tx {
+            Ok(tx_result) => {
+                println!("\n\nReclaim transaction successful");
+                println!("CUs Consumed: {}", tx_result.compute_units_consumed);
+                println!("Tx Signature: {}", tx_result.signature);
+
+                self.last_tx = Some(tx_result.clone());
+                self.last_tx_error = None;
+            }
+            Err(err) => {
+                self.last_tx = None;
+                self.last_tx_error = Some(format!("{:?}", err));
+            }
+        }
+
+        self
+    }
+
+    // Writes an escrow in one of the unversioned layouts, with `amount_to_give`
+    // of mint A in its ATA, as a program deployed before the layout changed
+    // would have left it. `None` gives the original 121-byte layout, an
+    // expiry the 129-byte one that stores it before the bump.
+    pub fn create_legacy_escrow(
+        self,
+        amount_to_give: u64,
+        seed: u64,
+        amount_to_receive: u64,
+        expires_at: Option<i64>,
+    ) -> Self {
+        let mut builder = self.set_escrow_accounts(seed);
+        let (escrow, bump) = builder.escrow.unwrap();
+        let mint_a = builder.mint_a.unwrap();
+
+        CreateAssociatedTokenAccount::new(&mut builder.svm, &builder.maker, &mint_a)
+            .owner(&escrow)
+            .token_program_id(&builder.token_program_a)
+            .send()
+            .unwrap();
+        MintTo::new(
+            &mut builder.svm,
+            &builder.maker,
+            &mint_a,
+            &builder.escrow_ata.unwrap(),
+            amount_to_give,
+        )
+        .token_program_id(&builder.token_program_a)
+        .send()
+        .unwrap();
+
+        let expires_at = expires_at.map(|expires_at| expires_at.to_le_bytes().to_vec()).unwrap_or_default();
+        let data = [
+            builder.maker.pubkey().to_bytes().as_slice(),
+            &mint_a.to_bytes(),
+            &builder.mint_b.unwrap().to_bytes(),
+            &amount_to_receive.to_le_bytes(),
+            &amount_to_give.to_le_bytes(),
+            &seed.to_le_bytes(),
+            expires_at.as_slice(),
+            &[bump],
+        ]
+        .concat();
+
+        let lamports = builder.svm.minimum_balance_for_rent_exemption(data.len());
+        builder
+            .svm
+            .set_account(
+                escrow,
+                Account {
+                    lamports,
+                    data,
+                    owner: PROGRAM_ID,
+                    executable: false,
+                    rent_epoch: 0,
+                },
+            )
+            .unwrap();
+
+        builder
+    }
+
+    pub fn execute_migrate(mut self) -> Self {
+        let migrate_data = vec![EscrowInstructions::Migrate as u8];
+
+        let migrate_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(self.maker.pubkey(), true),
+                AccountMeta::new(self.escrow.unwrap().0, false),
+                AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+            ],
+            data: migrate_data,
+        };
+
+        let tx = send_tx(&mut self.svm, &[migrate_ix], &self.maker, &[&self.maker]);
+
+        match &tx {
+            Ok(tx_result) => {
+                println!("\n\nMigrate transaction successful");
+                println!("CUs Consumed: {}", tx_result.compute_units_consumed);
+                println!("Tx Signature: {}", tx_result.signature);
+
+                self.last_tx = Some(tx_result.clone());
+                self.last_tx_error = None;
+            }
+            Err(err) => {
+                self.last_tx = None;
+                self.last_tx_error = Some(format!("{:?}", err));
+            }
+        }
+
+        self
+    }
+
+    pub fn now(&self) -> i64 {
+        self.svm.get_sysvar::<Clock>().unix_timestamp
+    }
+
+    pub fn warp_to_timestamp(mut self, unix_timestamp: i64) -> Self {
+        let mut clock = self.svm.get_sysvar::<Clock>();
+        clock.unix_timestamp = unix_timestamp;
+        self.svm.set_sysvar(&clock);
+
+        self
+    }
+
+    pub fn maker_lamports(&self) -> u64 {
+        self.svm.get_balance(&self.maker.pubkey()).unwrap_or(0)
+    }
+
+    pub fn taker_lamports(&self) -> u64 {
+        let taker = self.taker.as_ref().expect("Taker not created");
+        self.svm.get_balance(&taker.pubkey()).unwrap_or(0)
+    }
+
+    pub fn escrow_lamports(&self) -> u64 {
+        self.svm.get_balance(&self.escrow.unwrap().0).unwrap_or(0)
+    }
+
     pub fn escrow_ata_data(&self) -> TokenAccount {
         let account = self.svm.get_account(&self.escrow_ata.unwrap()).unwrap();
         unsafe { std::ptr::read(account.data.as_ptr() as *const TokenAccount) }
@@ -496,6 +1241,17 @@ impl EscrowTestBuilder {
         unsafe { std::ptr::read(data.as_ptr() as *const crate::state::Escrow) }
     }
 
+    pub fn escrow_data_for(&self, seed: u64) -> crate::state::Escrow {
+        let escrow_account = self.svm.get_account(&self.escrow_accounts_for(seed).0).unwrap();
+        let data = &escrow_account.data;
+        unsafe { std::ptr::read(data.as_ptr() as *const crate::state::Escrow) }
+    }
+
+    pub fn escrow_ata_data_for(&self, seed: u64) -> TokenAccount {
+        let account = self.svm.get_account(&self.escrow_accounts_for(seed).2).unwrap();
+        unsafe { std::ptr::read(account.data.as_ptr() as *const TokenAccount) }
+    }
+
     pub fn maker_ata_a_data(&self) -> TokenAccount {
         let account = self.svm.get_account(&self.maker_ata_a.unwrap()).unwrap();
         unsafe { std::ptr::read(account.data.as_ptr() as *const TokenAccount) }
@@ -547,4 +1303,10 @@ impl EscrowTestBuilder {
     pub fn last_tx_succeeded(&self) -> bool {
         self.last_tx_error.is_none()
     }
+
+    pub fn last_tx_failed_with(&self, error: crate::errors::EscrowError) -> bool {
+        self.last_tx_error
+            .as_ref()
+            .is_some_and(|err| err.contains(&format!("Custom({})", error as u32)))
+    }
 }
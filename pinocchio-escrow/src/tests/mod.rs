@@ -3,6 +3,8 @@ mod escrow_test_builder;
 #[cfg(test)]
 mod tests {
     use crate::tests::escrow_test_builder::EscrowTestBuilder;
+    use solana_native_token::LAMPORTS_PER_SOL;
+    use solana_signer::Signer;
 
     #[test]
     fn test_make() {
@@ -175,4 +177,1019 @@ mod tests {
 
         assert!(builder.is_escrow_closed(), "Escrow should be closed");
     }
+
+    #[test]
+    fn test_make_batch() {
+        let offers = [
+            (100u64, 1u64, 110u64),
+            (200u64, 2u64, 230u64),
+            (300u64, 3u64, 360u64),
+        ];
+        let total_given: u64 = offers.iter().map(|(give, _, _)| give).sum();
+
+        let builder = EscrowTestBuilder::new()
+            .create_mints()
+            .create_maker_ata_a()
+            .mint_to_maker_ata_a(total_given + 50)
+            .execute_make_batch(&offers);
+
+        assert!(builder.last_tx_succeeded());
+        assert_eq!(builder.maker_ata_a_data().amount(), 50);
+
+        for (amount_to_give, seed, amount_to_receive) in offers {
+            let escrow_ata_data = builder.escrow_ata_data_for(seed);
+            assert_eq!(escrow_ata_data.amount(), amount_to_give);
+            assert_eq!(escrow_ata_data.owner(), &builder.escrow_accounts_for(seed).0);
+            assert_eq!(escrow_ata_data.mint(), &builder.mint_a());
+
+            let escrow_data = builder.escrow_data_for(seed);
+            assert_eq!(escrow_data.seed(), seed);
+            assert_eq!(escrow_data.maker(), builder.maker_pubkey());
+            assert_eq!(escrow_data.mint_a(), builder.mint_a());
+            assert_eq!(escrow_data.mint_b(), builder.mint_b());
+            assert_eq!(escrow_data.amount_to_receive(), amount_to_receive);
+            assert_eq!(escrow_data.amount_to_give(), amount_to_give);
+            assert_eq!(escrow_data.bump, builder.escrow_accounts_for(seed).1);
+        }
+    }
+
+    #[test]
+    fn test_make_batch_then_take_one() {
+        let offers = [(100u64, 1u64, 110u64), (200u64, 2u64, 230u64)];
+
+        let builder = EscrowTestBuilder::new()
+            .create_mints()
+            .create_maker_ata_a()
+            .mint_to_maker_ata_a(300)
+            .execute_make_batch(&offers)
+            .set_escrow_accounts(2)
+            .setup_taker()
+            .create_maker_ata_b()
+            .create_taker_atas()
+            .mint_to_taker_ata_b(230)
+            .execute_take();
+
+        assert!(builder.last_tx_succeeded());
+        assert_eq!(builder.taker_ata_a_data().amount(), 200);
+        assert_eq!(builder.maker_ata_b_data().amount(), 230);
+        assert!(builder.is_escrow_closed(), "Taken escrow should be closed");
+
+        assert_eq!(builder.escrow_ata_data_for(1).amount(), 100);
+        assert_eq!(builder.escrow_data_for(1).amount_to_give(), 100);
+    }
+
+    #[test]
+    fn test_make_batch_fails_without_enough_tokens() {
+        let offers = [(100u64, 1u64, 110u64), (200u64, 2u64, 230u64)];
+
+        let builder = EscrowTestBuilder::new()
+            .create_mints()
+            .create_maker_ata_a()
+            .mint_to_maker_ata_a(150)
+            .execute_make_batch(&offers);
+
+        assert!(!builder.last_tx_succeeded());
+        assert_eq!(builder.maker_ata_a_data().amount(), 150);
+    }
+
+    #[test]
+    fn test_make_batch_rejects_non_mint_b() {
+        let offers = [(100u64, 1u64, 110u64)];
+
+        let builder = EscrowTestBuilder::new()
+            .create_mints()
+            .create_maker_ata_a()
+            .mint_to_maker_ata_a(100);
+        let not_a_mint = builder.maker_pubkey();
+        let builder = builder.execute_make_batch_with_mint_b(&offers, not_a_mint);
+
+        assert!(!builder.last_tx_succeeded());
+        assert_eq!(builder.maker_ata_a_data().amount(), 100);
+    }
+
+    #[test]
+    fn test_take_partial_multiple_takers() {
+        let deposit = 100u64;
+        let seed = 123u64;
+        let receive = 30u64;
+
+        // First taker pays 10 of 30: floor(10 * 100 / 30) = 33.
+        let builder = EscrowTestBuilder::new()
+            .create_mints()
+            .create_maker_ata_a()
+            .mint_to_maker_ata_a(deposit)
+            .set_escrow_accounts(seed)
+            .execute_make(deposit, seed, receive)
+            .create_maker_ata_b()
+            .setup_taker()
+            .create_taker_atas()
+            .mint_to_taker_ata_b(10)
+            .execute_take_partial(10);
+
+        assert!(builder.last_tx_succeeded());
+        assert_eq!(builder.taker_ata_a_data().amount(), 33);
+        assert_eq!(builder.escrow_ata_data().amount(), 67);
+        assert_eq!(builder.escrow_data().amount_to_give(), 67);
+        assert_eq!(builder.escrow_data().amount_to_receive(), 20);
+        assert!(!builder.is_escrow_closed());
+
+        // Second taker pays 10 of 20: floor(10 * 67 / 20) = 33, leaving the
+        // half-token of dust in the vault.
+        let builder = builder
+            .setup_taker()
+            .create_taker_atas()
+            .mint_to_taker_ata_b(10)
+            .execute_take_partial(10);
+
+        assert!(builder.last_tx_succeeded());
+        assert_eq!(builder.taker_ata_a_data().amount(), 33);
+        assert_eq!(builder.escrow_data().amount_to_give(), 34);
+        assert_eq!(builder.escrow_data().amount_to_receive(), 10);
+
+        // Third taker fills the rest and receives the dust.
+        let builder = builder
+            .setup_taker()
+            .create_taker_atas()
+            .mint_to_taker_ata_b(10)
+            .execute_take_partial(10);
+
+        assert!(builder.last_tx_succeeded());
+        assert_eq!(builder.taker_ata_a_data().amount(), 34);
+        assert_eq!(builder.maker_ata_b_data().amount(), receive);
+
+        assert!(builder.is_escrow_ata_closed(), "Escrow ATA should be closed");
+
+        assert!(builder.is_escrow_closed(), "Escrow should be closed");
+    }
+
+    #[test]
+    fn test_take_partial_rejects_dust_fill() {
+        let deposit = 10u64;
+        let seed = 123u64;
+        let receive = 100u64;
+
+        // floor(5 * 10 / 100) = 0, so the fill would pay nothing.
+        let builder = EscrowTestBuilder::new()
+            .create_mints()
+            .create_maker_ata_a()
+            .mint_to_maker_ata_a(deposit)
+            .set_escrow_accounts(seed)
+            .execute_make(deposit, seed, receive)
+            .create_maker_ata_b()
+            .setup_taker()
+            .create_taker_atas()
+            .mint_to_taker_ata_b(receive)
+            .execute_take_partial(5);
+
+        assert!(!builder.last_tx_succeeded());
+        assert_eq!(builder.taker_ata_b_data().amount(), receive);
+        assert_eq!(builder.escrow_data().amount_to_give(), deposit);
+        assert_eq!(builder.escrow_data().amount_to_receive(), receive);
+
+        // A fill of 10 pays exactly 1.
+        let builder = builder.execute_take_partial(10);
+        assert!(builder.last_tx_succeeded());
+        assert_eq!(builder.taker_ata_a_data().amount(), 1);
+    }
+
+    #[test]
+    fn test_take_partial_rejects_overfill() {
+        let deposit = 20u64;
+        let seed = 123u64;
+        let receive = 30u64;
+
+        let builder = EscrowTestBuilder::new()
+            .create_mints()
+            .create_maker_ata_a()
+            .mint_to_maker_ata_a(deposit)
+            .set_escrow_accounts(seed)
+            .execute_make(deposit, seed, receive)
+            .create_maker_ata_b()
+            .setup_taker()
+            .create_taker_atas()
+            .mint_to_taker_ata_b(receive + 1)
+            .execute_take_partial(receive + 1);
+
+        assert!(!builder.last_tx_succeeded());
+        assert!(!builder.is_escrow_closed());
+    }
+
+    #[test]
+    fn test_make_with_expiry() {
+        let deposit = 20u64;
+        let seed = 123u64;
+        let receive = 30u64;
+
+        let builder = EscrowTestBuilder::new()
+            .create_mints()
+            .create_maker_ata_a()
+            .mint_to_maker_ata_a(deposit)
+            .set_escrow_accounts(seed);
+        let expires_at = builder.now() + 3600;
+        let builder = builder.execute_make_with_expiry(deposit, seed, receive, Some(expires_at));
+
+        assert!(builder.last_tx_succeeded());
+        assert_eq!(builder.escrow_data().expires_at(), expires_at);
+        assert!(!builder.escrow_data().is_expired(expires_at - 1));
+        assert!(builder.escrow_data().is_expired(expires_at));
+    }
+
+    #[test]
+    fn test_make_without_expiry_never_expires() {
+        let builder = EscrowTestBuilder::new()
+            .create_mints()
+            .create_maker_ata_a()
+            .mint_to_maker_ata_a(20)
+            .set_escrow_accounts(123)
+            .execute_make(20, 123, 30);
+
+        assert_eq!(builder.escrow_data().expires_at(), 0);
+        assert!(!builder.escrow_data().is_expired(i64::MAX));
+    }
+
+    #[test]
+    fn test_take_after_expiry_fails() {
+        let deposit = 20u64;
+        let seed = 123u64;
+        let receive = 30u64;
+
+        let builder = EscrowTestBuilder::new()
+            .create_mints()
+            .create_maker_ata_a()
+            .mint_to_maker_ata_a(deposit)
+            .set_escrow_accounts(seed);
+        let expires_at = builder.now() + 3600;
+        let builder = builder
+            .execute_make_with_expiry(deposit, seed, receive, Some(expires_at))
+            .setup_taker()
+            .create_maker_ata_b()
+            .create_taker_atas()
+            .mint_to_taker_ata_b(receive)
+            .warp_to_timestamp(expires_at)
+            .execute_take();
+
+        assert!(!builder.last_tx_succeeded());
+        assert_eq!(builder.taker_ata_b_data().amount(), receive);
+        assert_eq!(builder.escrow_ata_data().amount(), deposit);
+
+        let builder = builder.execute_take_v2();
+        assert!(!builder.last_tx_succeeded());
+    }
+
+    #[test]
+    fn test_take_before_expiry_succeeds() {
+        let deposit = 20u64;
+        let seed = 123u64;
+        let receive = 30u64;
+
+        let builder = EscrowTestBuilder::new()
+            .create_mints()
+            .create_maker_ata_a()
+            .mint_to_maker_ata_a(deposit)
+            .set_escrow_accounts(seed);
+        let expires_at = builder.now() + 3600;
+        let builder = builder
+            .execute_make_with_expiry(deposit, seed, receive, Some(expires_at))
+            .setup_taker()
+            .create_maker_ata_b()
+            .create_taker_atas()
+            .mint_to_taker_ata_b(receive)
+            .warp_to_timestamp(expires_at - 1)
+            .execute_take();
+
+        assert!(builder.last_tx_succeeded());
+        assert_eq!(builder.taker_ata_a_data().amount(), deposit);
+        assert!(builder.is_escrow_closed(), "Escrow should be closed");
+    }
+
+    #[test]
+    fn test_reclaim_after_expiry() {
+        let deposit = 20u64;
+        let seed = 123u64;
+        let receive = 30u64;
+
+        let builder = EscrowTestBuilder::new()
+            .create_mints()
+            .create_maker_ata_a()
+            .mint_to_maker_ata_a(deposit)
+            .set_escrow_accounts(seed);
+        let expires_at = builder.now() + 3600;
+        let builder = builder
+            .execute_make_with_expiry(deposit, seed, receive, Some(expires_at))
+            .warp_to_timestamp(expires_at);
+        let maker_lamports = builder.maker_lamports();
+
+        let builder = builder.execute_reclaim();
+
+        assert!(builder.last_tx_succeeded());
+        assert_eq!(builder.maker_ata_a_data().amount(), deposit);
+        assert!(
+            builder.maker_lamports() > maker_lamports,
+            "Rent should go back to the maker"
+        );
+
+        assert!(builder.is_escrow_ata_closed(), "Escrow ATA should be closed");
+
+        assert!(builder.is_escrow_closed(), "Escrow should be closed");
+    }
+
+    #[test]
+    fn test_reclaim_before_expiry_fails() {
+        let deposit = 20u64;
+        let seed = 123u64;
+        let receive = 30u64;
+
+        let builder = EscrowTestBuilder::new()
+            .create_mints()
+            .create_maker_ata_a()
+            .mint_to_maker_ata_a(deposit)
+            .set_escrow_accounts(seed);
+        let expires_at = builder.now() + 3600;
+        let builder = builder
+            .execute_make_with_expiry(deposit, seed, receive, Some(expires_at))
+            .execute_reclaim();
+
+        assert!(!builder.last_tx_succeeded());
+        assert_eq!(builder.escrow_ata_data().amount(), deposit);
+        assert!(!builder.is_escrow_closed());
+    }
+
+    #[test]
+    fn test_reclaim_without_expiry_fails() {
+        let builder = EscrowTestBuilder::new()
+            .create_mints()
+            .create_maker_ata_a()
+            .mint_to_maker_ata_a(20)
+            .set_escrow_accounts(123)
+            .execute_make(20, 123, 30);
+        let far_future = builder.now() + 100 * 365 * 24 * 3600;
+        let builder = builder.warp_to_timestamp(far_future).execute_reclaim();
+
+        assert!(!builder.last_tx_succeeded());
+        assert!(!builder.is_escrow_closed());
+    }
+
+    #[test]
+    fn test_take_restricted_to_allowed_taker() {
+        let deposit = 20u64;
+        let seed = 123u64;
+        let receive = 30u64;
+
+        let builder = EscrowTestBuilder::new()
+            .create_mints()
+            .create_maker_ata_a()
+            .create_maker_ata_b()
+            .mint_to_maker_ata_a(deposit)
+            .set_escrow_accounts(seed)
+            .setup_taker();
+        let allowed_taker = builder.taker_keypair();
+        let builder = builder.execute_make_with_options(
+            deposit,
+            seed,
+            receive,
+            None,
+            Some(allowed_taker.pubkey()),
+        );
+
+        let escrow_data = builder.escrow_data();
+        assert_eq!(escrow_data.version, crate::state::Escrow::VERSION);
+        assert_eq!(escrow_data.allowed_taker(), Some(allowed_taker.pubkey()));
+        assert_eq!(escrow_data.expires_at(), 0);
+
+        let builder = builder
+            .setup_taker()
+            .create_taker_atas()
+            .mint_to_taker_ata_b(receive)
+            .execute_take();
+
+        assert!(!builder.last_tx_succeeded());
+
+        let builder = builder.execute_take_v2();
+
+        assert!(!builder.last_tx_succeeded());
+        assert_eq!(builder.escrow_ata_data().amount(), deposit);
+        assert!(!builder.is_escrow_closed());
+
+        let builder = builder
+            .set_taker(allowed_taker)
+            .create_taker_atas()
+            .mint_to_taker_ata_b(receive)
+            .execute_take();
+
+        assert!(builder.last_tx_succeeded());
+        assert_eq!(builder.taker_ata_a_data().amount(), deposit);
+        assert_eq!(builder.maker_ata_b_data().amount(), receive);
+        assert!(builder.is_escrow_closed(), "Escrow should be closed");
+    }
+
+    #[test]
+    fn test_make_without_allowed_taker_is_public() {
+        let builder = EscrowTestBuilder::new()
+            .create_mints()
+            .create_maker_ata_a()
+            .mint_to_maker_ata_a(20)
+            .set_escrow_accounts(123)
+            .execute_make(20, 123, 30);
+
+        let escrow_data = builder.escrow_data();
+        assert_eq!(escrow_data.version, crate::state::Escrow::VERSION);
+        assert_eq!(escrow_data.allowed_taker(), None);
+    }
+
+    #[test]
+    fn test_take_with_token_2022_mints() {
+        let deposit = 20u64;
+        let seed = 123u64;
+        let receive = 30u64;
+
+        let builder = EscrowTestBuilder::new()
+            .create_mints()
+            .create_token_2022_mint_a(None)
+            .create_token_2022_mint_b(None)
+            .create_maker_ata_a()
+            .mint_to_maker_ata_a(deposit)
+            .set_escrow_accounts(seed)
+            .execute_make(deposit, seed, receive)
+            .setup_taker()
+            .create_maker_ata_b()
+            .create_taker_atas()
+            .mint_to_taker_ata_b(receive)
+            .execute_take();
+
+        assert!(builder.last_tx_succeeded());
+        assert_eq!(builder.taker_ata_a_data().amount(), deposit);
+        assert_eq!(builder.maker_ata_b_data().amount(), receive);
+        assert!(builder.is_escrow_ata_closed(), "Escrow ATA should be closed");
+        assert!(builder.is_escrow_closed(), "Escrow should be closed");
+    }
+
+    #[test]
+    fn test_take_with_transfer_fee_on_mint_b() {
+        let deposit = 20u64;
+        let seed = 123u64;
+        let receive = 1000u64;
+
+        // 1% fee: the taker sends 1011 so that the maker nets 1000.
+        let builder = EscrowTestBuilder::new()
+            .create_mints()
+            .create_token_2022_mint_b(Some(100))
+            .create_maker_ata_a()
+            .mint_to_maker_ata_a(deposit)
+            .set_escrow_accounts(seed)
+            .execute_make(deposit, seed, receive)
+            .setup_taker()
+            .create_maker_ata_b()
+            .create_taker_atas()
+            .mint_to_taker_ata_b(2000)
+            .execute_take();
+
+        assert!(builder.last_tx_succeeded());
+        assert_eq!(builder.taker_ata_a_data().amount(), deposit);
+        assert_eq!(builder.maker_ata_b_data().amount(), receive);
+        assert_eq!(builder.taker_ata_b_data().amount(), 2000 - 1011);
+        assert!(builder.is_escrow_closed(), "Escrow should be closed");
+    }
+
+    #[test]
+    fn test_make_with_transfer_fee_on_mint_a() {
+        let deposit = 1000u64;
+        let seed = 123u64;
+        let receive = 30u64;
+
+        // 1% fee on the way into the vault and again on the way out.
+        let builder = EscrowTestBuilder::new()
+            .create_mints()
+            .create_token_2022_mint_a(Some(100))
+            .create_maker_ata_a()
+            .mint_to_maker_ata_a(deposit)
+            .set_escrow_accounts(seed)
+            .execute_make(deposit, seed, receive);
+
+        assert_eq!(builder.escrow_data().amount_to_give(), 990);
+        assert_eq!(builder.escrow_ata_data().amount(), 990);
+
+        let builder = builder
+            .setup_taker()
+            .create_maker_ata_b()
+            .create_taker_atas()
+            .mint_to_taker_ata_b(receive)
+            .execute_take();
+
+        assert!(builder.last_tx_succeeded());
+        assert_eq!(builder.taker_ata_a_data().amount(), 980);
+        assert_eq!(builder.maker_ata_b_data().amount(), receive);
+        assert!(builder.is_escrow_ata_closed(), "Escrow ATA should be closed");
+        assert!(builder.is_escrow_closed(), "Escrow should be closed");
+    }
+
+    #[test]
+    fn test_cancel_with_token_2022_mint_a() {
+        let deposit = 20u64;
+        let seed = 123u64;
+        let receive = 30u64;
+
+        let builder = EscrowTestBuilder::new()
+            .create_mints()
+            .create_token_2022_mint_a(None)
+            .create_maker_ata_a()
+            .mint_to_maker_ata_a(deposit)
+            .set_escrow_accounts(seed)
+            .execute_make(deposit, seed, receive)
+            .execute_cancel();
+
+        assert!(builder.last_tx_succeeded());
+        assert_eq!(builder.maker_ata_a_data().amount(), deposit);
+        assert!(builder.is_escrow_ata_closed(), "Escrow ATA should be closed");
+        assert!(builder.is_escrow_closed(), "Escrow should be closed");
+    }
+
+    #[test]
+    fn test_take_sol_for_token() {
+        let deposit = 2 * LAMPORTS_PER_SOL;
+        let seed = 123u64;
+        let receive = 30u64;
+
+        let builder = EscrowTestBuilder::new()
+            .create_mints()
+            .use_native_mint_a()
+            .set_escrow_accounts(seed)
+            .execute_make(deposit, seed, receive);
+
+        assert!(builder.escrow_lamports() > deposit);
+        assert_eq!(builder.escrow_data().amount_to_give(), deposit);
+
+        let builder = builder
+            .setup_taker()
+            .create_maker_ata_b()
+            .create_taker_atas()
+            .mint_to_taker_ata_b(receive);
+        let taker_lamports = builder.taker_lamports();
+        let builder = builder.execute_take();
+
+        assert!(builder.last_tx_succeeded());
+        assert!(
+            builder.taker_lamports() > taker_lamports + deposit - LAMPORTS_PER_SOL / 100,
+            "Taker should receive the SOL deposit"
+        );
+        assert_eq!(builder.taker_ata_b_data().amount(), 0);
+        assert_eq!(builder.maker_ata_b_data().amount(), receive);
+        assert!(builder.is_escrow_closed(), "Escrow should be closed");
+    }
+
+    #[test]
+    fn test_take_token_for_sol() {
+        let deposit = 20u64;
+        let seed = 123u64;
+        let receive = LAMPORTS_PER_SOL;
+
+        let builder = EscrowTestBuilder::new()
+            .create_mints()
+            .use_native_mint_b()
+            .create_maker_ata_a()
+            .mint_to_maker_ata_a(deposit)
+            .set_escrow_accounts(seed)
+            .execute_make(deposit, seed, receive)
+            .setup_taker()
+            .create_taker_atas();
+        let maker_lamports = builder.maker_lamports();
+        let taker_lamports = builder.taker_lamports();
+        let builder = builder.execute_take();
+
+        assert!(builder.last_tx_succeeded());
+        assert_eq!(builder.taker_ata_a_data().amount(), deposit);
+        assert!(
+            builder.maker_lamports() > maker_lamports + receive,
+            "Maker should receive the SOL payment and the rent"
+        );
+        assert!(builder.taker_lamports() < taker_lamports - receive);
+        assert!(builder.is_escrow_ata_closed(), "Escrow ATA should be closed");
+        assert!(builder.is_escrow_closed(), "Escrow should be closed");
+    }
+
+    #[test]
+    fn test_cancel_sol_deposit() {
+        let deposit = 2 * LAMPORTS_PER_SOL;
+        let seed = 123u64;
+        let receive = 30u64;
+
+        let builder = EscrowTestBuilder::new()
+            .create_mints()
+            .use_native_mint_a()
+            .set_escrow_accounts(seed)
+            .execute_make(deposit, seed, receive);
+        let maker_lamports = builder.maker_lamports();
+        let builder = builder.execute_cancel();
+
+        assert!(builder.last_tx_succeeded());
+        assert!(
+            builder.maker_lamports() > maker_lamports + deposit,
+            "Maker should get the deposit and the rent back"
+        );
+        assert!(builder.is_escrow_closed(), "Escrow should be closed");
+    }
+
+    #[test]
+    fn test_amend_reprice_then_take() {
+        let deposit = 20u64;
+        let seed = 123u64;
+        let receive = 30u64;
+
+        let builder = EscrowTestBuilder::new()
+            .create_mints()
+            .create_maker_ata_a()
+            .mint_to_maker_ata_a(deposit)
+            .set_escrow_accounts(seed)
+            .execute_make(deposit, seed, receive)
+            .execute_amend(deposit, 45);
+
+        assert!(builder.last_tx_succeeded());
+        assert_eq!(builder.escrow_data().amount_to_receive(), 45);
+        assert_eq!(builder.escrow_data().amount_to_give(), deposit);
+
+        let builder = builder
+            .setup_taker()
+            .create_maker_ata_b()
+            .create_taker_atas()
+            .mint_to_taker_ata_b(45)
+            .execute_take();
+
+        assert!(builder.last_tx_succeeded());
+        assert_eq!(builder.taker_ata_a_data().amount(), deposit);
+        assert_eq!(builder.maker_ata_b_data().amount(), 45);
+        assert!(builder.is_escrow_closed(), "Escrow should be closed");
+    }
+
+    #[test]
+    fn test_amend_top_up_and_withdraw() {
+        let seed = 123u64;
+        let receive = 30u64;
+
+        let builder = EscrowTestBuilder::new()
+            .create_mints()
+            .create_maker_ata_a()
+            .mint_to_maker_ata_a(100)
+            .set_escrow_accounts(seed)
+            .execute_make(20, seed, receive)
+            .execute_amend(50, receive);
+
+        assert!(builder.last_tx_succeeded());
+        assert_eq!(builder.escrow_data().amount_to_give(), 50);
+        assert_eq!(builder.escrow_ata_data().amount(), 50);
+        assert_eq!(builder.maker_ata_a_data().amount(), 50);
+
+        let builder = builder.execute_amend(15, receive);
+
+        assert!(builder.last_tx_succeeded());
+        assert_eq!(builder.escrow_data().amount_to_give(), 15);
+        assert_eq!(builder.escrow_ata_data().amount(), 15);
+        assert_eq!(builder.maker_ata_a_data().amount(), 85);
+        assert_eq!(builder.escrow_data().amount_to_receive(), receive);
+    }
+
+    #[test]
+    fn test_amend_rejects_zero_amounts() {
+        let deposit = 20u64;
+        let seed = 123u64;
+        let receive = 30u64;
+
+        let builder = EscrowTestBuilder::new()
+            .create_mints()
+            .create_maker_ata_a()
+            .mint_to_maker_ata_a(deposit)
+            .set_escrow_accounts(seed)
+            .execute_make(deposit, seed, receive)
+            .execute_amend(0, receive);
+
+        assert!(!builder.last_tx_succeeded());
+
+        let builder = builder.execute_amend(deposit, 0);
+
+        assert!(!builder.last_tx_succeeded());
+        assert_eq!(builder.escrow_data().amount_to_give(), deposit);
+        assert_eq!(builder.escrow_data().amount_to_receive(), receive);
+        assert_eq!(builder.escrow_ata_data().amount(), deposit);
+    }
+
+    #[test]
+    fn test_amend_sol_deposit() {
+        let deposit = 2 * LAMPORTS_PER_SOL;
+        let seed = 123u64;
+        let receive = 30u64;
+
+        let builder = EscrowTestBuilder::new()
+            .create_mints()
+            .use_native_mint_a()
+            .set_escrow_accounts(seed)
+            .execute_make(deposit, seed, receive);
+        let escrow_lamports = builder.escrow_lamports();
+        let builder = builder.execute_amend(LAMPORTS_PER_SOL, receive);
+
+        assert!(builder.last_tx_succeeded());
+        assert_eq!(builder.escrow_data().amount_to_give(), LAMPORTS_PER_SOL);
+        assert_eq!(builder.escrow_lamports(), escrow_lamports - LAMPORTS_PER_SOL);
+    }
+
+    #[test]
+    fn test_make_rejects_truncated_expiry() {
+        let builder = EscrowTestBuilder::new()
+            .create_mints()
+            .create_maker_ata_a()
+            .mint_to_maker_ata_a(100)
+            .set_escrow_accounts(1)
+            .execute_make_with_raw_tail(100, 1, 110, &[0; 4]);
+
+        assert!(!builder.last_tx_succeeded());
+        assert_eq!(builder.maker_ata_a_data().amount(), 100);
+    }
+
+    #[test]
+    fn test_legacy_escrow_is_migrated_then_cancelled() {
+        let builder = EscrowTestBuilder::new()
+            .create_mints()
+            .create_maker_ata_a()
+            .create_legacy_escrow(100, 7, 110, None)
+            .execute_cancel();
+
+        assert!(!builder.last_tx_succeeded(), "Cancel reads only the current layout");
+
+        let builder = builder.execute_migrate();
+        assert!(builder.last_tx_succeeded());
+
+        let escrow_data = builder.escrow_data();
+        assert_eq!(escrow_data.version, crate::state::Escrow::VERSION);
+        assert_eq!(escrow_data.maker(), builder.maker_pubkey());
+        assert_eq!(escrow_data.mint_a(), builder.mint_a());
+        assert_eq!(escrow_data.mint_b(), builder.mint_b());
+        assert_eq!(escrow_data.amount_to_give(), 100);
+        assert_eq!(escrow_data.amount_to_receive(), 110);
+        assert_eq!(escrow_data.seed(), 7);
+        assert_eq!(escrow_data.expires_at(), 0);
+        assert_eq!(escrow_data.allowed_taker(), None);
+        assert_eq!(escrow_data.bump, builder.escrow_bump());
+
+        let builder = builder.execute_cancel();
+        assert!(builder.last_tx_succeeded());
+        assert_eq!(builder.maker_ata_a_data().amount(), 100);
+        assert!(builder.is_escrow_closed());
+    }
+
+    #[test]
+    fn test_legacy_escrow_with_expiry_is_migrated_then_reclaimed() {
+        let builder = EscrowTestBuilder::new()
+            .create_mints()
+            .create_maker_ata_a();
+        let expires_at = builder.now() + 3600;
+        let builder = builder
+            .create_legacy_escrow(100, 7, 110, Some(expires_at))
+            .warp_to_timestamp(expires_at)
+            .execute_reclaim();
+
+        assert!(!builder.last_tx_succeeded(), "Reclaim reads only the current layout");
+
+        let builder = builder.execute_migrate();
+        assert!(builder.last_tx_succeeded());
+        assert_eq!(builder.escrow_data().version, crate::state::Escrow::VERSION);
+        assert_eq!(builder.escrow_data().expires_at(), expires_at);
+        assert_eq!(builder.escrow_data().amount_to_give(), 100);
+
+        let builder = builder.execute_reclaim();
+        assert!(builder.last_tx_succeeded());
+        assert_eq!(builder.maker_ata_a_data().amount(), 100);
+        assert!(builder.is_escrow_closed());
+    }
+
+    #[test]
+    fn test_migrate_rejects_current_escrow() {
+        let builder = EscrowTestBuilder::new()
+            .create_mints()
+            .create_maker_ata_a()
+            .mint_to_maker_ata_a(100)
+            .set_escrow_accounts(1)
+            .execute_make(100, 1, 110)
+            .execute_migrate();
+
+        assert!(!builder.last_tx_succeeded());
+        assert_eq!(builder.escrow_data().amount_to_give(), 100);
+    }
+
+    #[test]
+    fn test_make_and_take_with_transfer_hook_on_mint_a() {
+        if EscrowTestBuilder::transfer_hook_program_missing() {
+            return;
+        }
+
+        let builder = EscrowTestBuilder::new()
+            .create_mints()
+            .create_transfer_hook_mint_a()
+            .create_maker_ata_a()
+            .mint_to_maker_ata_a(100)
+            .set_escrow_accounts(1)
+            .execute_make(100, 1, 110);
+
+        assert!(builder.last_tx_succeeded());
+        assert_eq!(builder.escrow_ata_data().amount(), 100);
+
+        let builder = builder
+            .setup_taker()
+            .create_maker_ata_b()
+            .create_taker_atas()
+            .mint_to_taker_ata_b(110)
+            .execute_take();
+
+        assert!(builder.last_tx_succeeded());
+        assert_eq!(builder.taker_ata_a_data().amount(), 100);
+        assert_eq!(builder.maker_ata_b_data().amount(), 110);
+        assert!(builder.is_escrow_closed());
+    }
+
+    #[test]
+    fn test_cancel_with_transfer_hook_on_mint_a() {
+        if EscrowTestBuilder::transfer_hook_program_missing() {
+            return;
+        }
+
+        let builder = EscrowTestBuilder::new()
+            .create_mints()
+            .create_transfer_hook_mint_a()
+            .create_maker_ata_a()
+            .mint_to_maker_ata_a(100)
+            .set_escrow_accounts(1)
+            .execute_make(100, 1, 110)
+            .execute_cancel();
+
+        assert!(builder.last_tx_succeeded());
+        assert_eq!(builder.maker_ata_a_data().amount(), 100);
+        assert!(builder.is_escrow_closed());
+    }
+
+    #[test]
+    fn test_amend_with_transfer_hook_on_mint_a() {
+        if EscrowTestBuilder::transfer_hook_program_missing() {
+            return;
+        }
+
+        let builder = EscrowTestBuilder::new()
+            .create_mints()
+            .create_transfer_hook_mint_a()
+            .create_maker_ata_a()
+            .mint_to_maker_ata_a(150)
+            .set_escrow_accounts(1)
+            .execute_make(100, 1, 110)
+            .execute_amend(150, 110);
+
+        assert!(builder.last_tx_succeeded(), "Top-up should run the hook");
+        assert_eq!(builder.escrow_ata_data().amount(), 150);
+        assert_eq!(builder.maker_ata_a_data().amount(), 0);
+
+        let builder = builder.execute_amend(40, 110);
+        assert!(builder.last_tx_succeeded(), "Withdrawal should run the hook");
+        assert_eq!(builder.escrow_ata_data().amount(), 40);
+        assert_eq!(builder.maker_ata_a_data().amount(), 110);
+        assert_eq!(builder.escrow_data().amount_to_give(), 40);
+    }
+
+    #[test]
+    fn test_reclaim_with_transfer_hook_on_mint_a() {
+        if EscrowTestBuilder::transfer_hook_program_missing() {
+            return;
+        }
+
+        let builder = EscrowTestBuilder::new()
+            .create_mints()
+            .create_transfer_hook_mint_a()
+            .create_maker_ata_a()
+            .mint_to_maker_ata_a(100)
+            .set_escrow_accounts(1);
+        let expires_at = builder.now() + 3600;
+        let builder = builder
+            .execute_make_with_expiry(100, 1, 110, Some(expires_at))
+            .warp_to_timestamp(expires_at)
+            .execute_reclaim();
+
+        assert!(builder.last_tx_succeeded());
+        assert_eq!(builder.maker_ata_a_data().amount(), 100);
+        assert!(builder.is_escrow_closed());
+    }
+
+    #[test]
+    fn test_take_with_transfer_hooks_on_both_mints() {
+        if EscrowTestBuilder::transfer_hook_program_missing() {
+            return;
+        }
+
+        let builder = EscrowTestBuilder::new()
+            .create_transfer_hook_mint_a()
+            .create_transfer_hook_mint_b()
+            .create_maker_ata_a()
+            .mint_to_maker_ata_a(100)
+            .set_escrow_accounts(1)
+            .execute_make(100, 1, 110)
+            .setup_taker()
+            .create_maker_ata_b()
+            .create_taker_atas()
+            .mint_to_taker_ata_b(110)
+            .execute_take();
+
+        assert!(builder.last_tx_succeeded());
+        assert_eq!(builder.taker_ata_a_data().amount(), 100);
+        assert_eq!(builder.maker_ata_b_data().amount(), 110);
+        assert!(builder.is_escrow_closed());
+    }
+
+    #[test]
+    fn test_token_only_instructions_reject_native_mint() {
+        let deposit = 2 * LAMPORTS_PER_SOL;
+        let seed = 123u64;
+        let receive = 30u64;
+
+        let builder = EscrowTestBuilder::new()
+            .create_mints()
+            .use_native_mint_a()
+            .set_escrow_accounts(seed)
+            .execute_make(deposit, seed, receive)
+            .execute_cancel_v2();
+
+        assert!(builder.last_tx_failed_with(crate::errors::EscrowError::NativeNotSupported));
+
+        let builder = builder
+            .setup_taker()
+            .create_maker_ata_b()
+            .create_taker_atas()
+            .mint_to_taker_ata_b(receive)
+            .execute_take_v2();
+
+        assert!(builder.last_tx_failed_with(crate::errors::EscrowError::NativeNotSupported));
+        assert_eq!(builder.escrow_data().amount_to_give(), deposit);
+
+        let builder = builder.execute_cancel();
+        assert!(builder.last_tx_succeeded());
+        assert!(builder.is_escrow_closed());
+    }
+
+    #[test]
+    fn test_take_with_stale_offer_after_amend_fails() {
+        let builder = EscrowTestBuilder::new()
+            .create_mints()
+            .create_maker_ata_a()
+            .mint_to_maker_ata_a(100)
+            .set_escrow_accounts(1)
+            .execute_make(100, 1, 110)
+            .setup_taker()
+            .create_maker_ata_b()
+            .create_taker_atas()
+            .mint_to_taker_ata_b(200)
+            .execute_amend(50, 200)
+            .execute_take_expecting(100, 110);
+
+        assert!(builder.last_tx_failed_with(crate::errors::EscrowError::OfferChanged));
+        assert_eq!(builder.taker_ata_b_data().amount(), 200);
+        assert_eq!(builder.escrow_ata_data().amount(), 50);
+
+        let builder = builder.execute_take_expecting(50, 200);
+        assert!(builder.last_tx_succeeded());
+        assert_eq!(builder.taker_ata_a_data().amount(), 50);
+        assert_eq!(builder.maker_ata_b_data().amount(), 200);
+        assert!(builder.is_escrow_closed());
+    }
+
+    #[test]
+    fn test_take_partial_with_stale_offer_after_amend_fails() {
+        let builder = EscrowTestBuilder::new()
+            .create_mints()
+            .create_maker_ata_a()
+            .mint_to_maker_ata_a(100)
+            .set_escrow_accounts(1)
+            .execute_make(100, 1, 30)
+            .setup_taker()
+            .create_maker_ata_b()
+            .create_taker_atas()
+            .mint_to_taker_ata_b(10)
+            .execute_amend(100, 60)
+            .execute_take_partial_expecting(10, 100, 30);
+
+        assert!(builder.last_tx_failed_with(crate::errors::EscrowError::OfferChanged));
+        assert_eq!(builder.taker_ata_b_data().amount(), 10);
+
+        let builder = builder.execute_take_partial_expecting(10, 100, 60);
+        assert!(builder.last_tx_succeeded());
+        assert_eq!(builder.maker_ata_b_data().amount(), 10);
+        assert_eq!(builder.escrow_data().amount_to_receive(), 50);
+    }
+
+    #[test]
+    fn test_amend_expired_escrow_fails() {
+        let builder = EscrowTestBuilder::new()
+            .create_mints()
+            .create_maker_ata_a()
+            .mint_to_maker_ata_a(100)
+            .set_escrow_accounts(1);
+        let expires_at = builder.now() + 3600;
+        let builder = builder
+            .execute_make_with_expiry(100, 1, 110, Some(expires_at))
+            .warp_to_timestamp(expires_at)
+            .execute_amend(100, 90);
+
+        assert!(builder.last_tx_failed_with(crate::errors::EscrowError::Expired));
+        assert_eq!(builder.escrow_data().amount_to_receive(), 110);
+    }
 }
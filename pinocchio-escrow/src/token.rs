@@ -0,0 +1,271 @@
+use pinocchio::{
+    cpi::{slice_invoke_signed, Signer},
+    error::ProgramError,
+    instruction::{InstructionAccount, InstructionView},
+    sysvars::{clock::Clock, Sysvar},
+    AccountView, Address, ProgramResult,
+};
+use pinocchio_token::state::{Mint, TokenAccount};
+
+// TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb
+pub const TOKEN_2022_ID: Address = Address::new_from_array([
+    6, 221, 246, 225, 238, 117, 143, 222, 24, 66, 93, 188, 228, 108, 205, 218, 182, 26, 252, 77,
+    131, 185, 13, 39, 254, 189, 249, 40, 216, 161, 139, 252,
+]);
+
+// Token-2022 keeps the SPL Token base layouts and appends an account type
+// byte plus TLV extensions after the first `TokenAccount::LEN` bytes.
+const ACCOUNT_TYPE_OFFSET: usize = TokenAccount::LEN;
+const ACCOUNT_TYPE_MINT: u8 = 1;
+const ACCOUNT_TYPE_ACCOUNT: u8 = 2;
+
+const EXTENSION_UNINITIALIZED: u16 = 0;
+const EXTENSION_TRANSFER_FEE_CONFIG: u16 = 1;
+const TRANSFER_FEE_CONFIG_LEN: usize = 108;
+const OLDER_TRANSFER_FEE_OFFSET: usize = 72;
+const NEWER_TRANSFER_FEE_OFFSET: usize = 90;
+const MAX_FEE_BASIS_POINTS: u128 = 10_000;
+const TRANSFER_FEE_EXTENSION: u8 = 26;
+const HARVEST_WITHHELD_TOKENS_TO_MINT: u8 = 4;
+
+pub fn is_token_program(address: &Address) -> bool {
+    address == &pinocchio_token::ID || address == &TOKEN_2022_ID
+}
+
+pub fn check_token_program(token_program: &AccountView) -> ProgramResult {
+    if !is_token_program(token_program.address()) {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    Ok(())
+}
+
+fn check_account_type(data: &[u8], base_len: usize, account_type: u8) -> ProgramResult {
+    if data.len() < base_len {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if data.len() > base_len && data.get(ACCOUNT_TYPE_OFFSET) != Some(&account_type) {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(())
+}
+
+// Returns the mint's decimals, for `TransferChecked`. The mint has to belong
+// to one of the two token programs; callers that know which one check
+// `mint.owner()` against their token program account as well.
+pub fn mint_decimals(mint: &AccountView) -> Result<u8, ProgramError> {
+    if !is_token_program(unsafe { mint.owner() }) {
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    let data = mint.try_borrow()?;
+    check_account_type(&data, Mint::LEN, ACCOUNT_TYPE_MINT)?;
+
+    let mint_state = unsafe { Mint::from_bytes_unchecked(&data[..Mint::LEN]) };
+    if !mint_state.is_initialized() {
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    Ok(mint_state.decimals())
+}
+
+// Checks that `account` is a token account of `mint`, owned by `owner` and
+// held by the same token program as the mint, and returns its balance.
+pub fn check_token_account(
+    account: &AccountView,
+    mint: &AccountView,
+    owner: &Address,
+) -> Result<u64, ProgramError> {
+    if unsafe { account.owner() != mint.owner() } {
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    let data = account.try_borrow()?;
+    check_account_type(&data, TokenAccount::LEN, ACCOUNT_TYPE_ACCOUNT)?;
+
+    let account_state = unsafe { TokenAccount::from_bytes_unchecked(&data[..TokenAccount::LEN]) };
+    if account_state.mint() != mint.address() {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if account_state.owner() != owner {
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    Ok(account_state.amount())
+}
+
+// The amount to send so that `amount` arrives after the mint's transfer fee,
+// mirroring Token-2022's `calculate_inverse_epoch_fee`. Mints without the
+// transfer fee extension charge nothing.
+pub fn amount_with_transfer_fee(mint: &AccountView, amount: u64) -> Result<u64, ProgramError> {
+    let Some((maximum_fee, basis_points)) = transfer_fee(mint)? else {
+        return Ok(amount);
+    };
+
+    let fee = match u128::from(basis_points) {
+        0 => 0,
+        MAX_FEE_BASIS_POINTS => maximum_fee,
+        basis_points => {
+            let amount = u128::from(amount);
+            let gross =
+                (amount * MAX_FEE_BASIS_POINTS).div_ceil(MAX_FEE_BASIS_POINTS - basis_points);
+            u64::try_from(gross - amount).map_or(maximum_fee, |fee| fee.min(maximum_fee))
+        }
+    };
+
+    amount.checked_add(fee).ok_or(ProgramError::ArithmeticOverflow)
+}
+
+// `(maximum_fee, transfer_fee_basis_points)` in effect this epoch.
+fn transfer_fee(mint: &AccountView) -> Result<Option<(u64, u16)>, ProgramError> {
+    if unsafe { mint.owner() } != &TOKEN_2022_ID {
+        return Ok(None);
+    }
+
+    let data = mint.try_borrow()?;
+    let mut extensions = match data.get(ACCOUNT_TYPE_OFFSET + 1..) {
+        Some(extensions) => extensions,
+        None => return Ok(None),
+    };
+
+    while let Some((header, rest)) = extensions.split_at_checked(4) {
+        let extension_type = u16::from_le_bytes([header[0], header[1]]);
+        let len = u16::from_le_bytes([header[2], header[3]]) as usize;
+        let (value, rest) = rest
+            .split_at_checked(len)
+            .ok_or(ProgramError::InvalidAccountData)?;
+
+        match extension_type {
+            EXTENSION_UNINITIALIZED => break,
+            EXTENSION_TRANSFER_FEE_CONFIG if len == TRANSFER_FEE_CONFIG_LEN => {
+                let newer_epoch = read_u64(value, NEWER_TRANSFER_FEE_OFFSET);
+                let offset = if Clock::get()?.epoch >= newer_epoch {
+                    NEWER_TRANSFER_FEE_OFFSET
+                } else {
+                    OLDER_TRANSFER_FEE_OFFSET
+                };
+                let maximum_fee = read_u64(value, offset + 8);
+                let basis_points = u16::from_le_bytes([value[offset + 16], value[offset + 17]]);
+                return Ok(Some((maximum_fee, basis_points)));
+            }
+            _ => extensions = rest,
+        }
+    }
+
+    Ok(None)
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    let mut value = [0; 8];
+    value.copy_from_slice(&bytes[offset..offset + 8]);
+    u64::from_le_bytes(value)
+}
+
+// `TransferChecked` against whichever token program owns the mint.
+// `extra_accounts` are forwarded as-is, so Token-2022 can find the accounts a
+// transfer hook needs; they are left out for SPL Token, which would read them
+// as multisig signers.
+pub struct TransferChecked<'a> {
+    pub from: &'a AccountView,
+    pub mint: &'a AccountView,
+    pub to: &'a AccountView,
+    pub authority: &'a AccountView,
+    pub amount: u64,
+    pub decimals: u8,
+    pub token_program: &'a AccountView,
+    pub extra_accounts: &'a [AccountView],
+}
+
+impl TransferChecked<'_> {
+    pub fn invoke(&self) -> ProgramResult {
+        self.invoke_signed(&[])
+    }
+
+    pub fn invoke_signed(&self, signers: &[Signer]) -> ProgramResult {
+        let extra_accounts = if self.token_program.address() == &TOKEN_2022_ID {
+            self.extra_accounts
+        } else {
+            &[]
+        };
+
+        let mut instruction_accounts = Vec::with_capacity(4 + extra_accounts.len());
+        instruction_accounts.extend([
+            InstructionAccount::writable(self.from.address()),
+            InstructionAccount::readonly(self.mint.address()),
+            InstructionAccount::writable(self.to.address()),
+            InstructionAccount::readonly_signer(self.authority.address()),
+        ]);
+        instruction_accounts.extend(extra_accounts.iter().map(|account| {
+            InstructionAccount::new(account.address(), account.is_writable(), account.is_signer())
+        }));
+
+        let mut account_views = Vec::with_capacity(4 + extra_accounts.len());
+        account_views.extend([self.from, self.mint, self.to, self.authority]);
+        account_views.extend(extra_accounts.iter());
+
+        let mut instruction_data = [0; 10];
+        instruction_data[0] = 12;
+        instruction_data[1..9].copy_from_slice(&self.amount.to_le_bytes());
+        instruction_data[9] = self.decimals;
+
+        let instruction = InstructionView {
+            program_id: self.token_program.address(),
+            accounts: &instruction_accounts,
+            data: &instruction_data,
+        };
+
+        slice_invoke_signed(&instruction, &account_views, signers)
+    }
+}
+
+// `CloseAccount` against either token program. Token-2022 refuses to close
+// an account holding withheld transfer fees, so for fee mints these are
+// harvested to the mint first; that is permissionless but needs the mint
+// writable.
+pub struct CloseAccount<'a> {
+    pub account: &'a AccountView,
+    pub mint: &'a AccountView,
+    pub destination: &'a AccountView,
+    pub authority: &'a AccountView,
+    pub token_program: &'a AccountView,
+}
+
+impl CloseAccount<'_> {
+    pub fn invoke_signed(&self, signers: &[Signer]) -> ProgramResult {
+        if transfer_fee(self.mint)?.is_some() {
+            self.harvest_withheld_tokens()?;
+        }
+
+        let instruction_accounts = [
+            InstructionAccount::writable(self.account.address()),
+            InstructionAccount::writable(self.destination.address()),
+            InstructionAccount::readonly_signer(self.authority.address()),
+        ];
+
+        let instruction = InstructionView {
+            program_id: self.token_program.address(),
+            accounts: &instruction_accounts,
+            data: &[9],
+        };
+
+        slice_invoke_signed(
+            &instruction,
+            &[self.account, self.destination, self.authority],
+            signers,
+        )
+    }
+
+    fn harvest_withheld_tokens(&self) -> ProgramResult {
+        let instruction_accounts = [
+            InstructionAccount::writable(self.mint.address()),
+            InstructionAccount::writable(self.account.address()),
+        ];
+
+        let instruction = InstructionView {
+            program_id: self.token_program.address(),
+            accounts: &instruction_accounts,
+            data: &[TRANSFER_FEE_EXTENSION, HARVEST_WITHHELD_TOKENS_TO_MINT],
+        };
+
+        slice_invoke_signed(&instruction, &[self.mint, self.account], &[])
+    }
+}
@@ -0,0 +1,221 @@
+use std::hint::black_box;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use criterion::{
+    BatchSize, BenchmarkGroup, BenchmarkId, Criterion, Throughput, criterion_group, criterion_main,
+    measurement::WallTime,
+};
+use serde::{Deserialize, Serialize};
+use wincode::{SchemaRead, SchemaWrite};
+
+use rust_generic_storage::{
+    serializer::{
+        Serializer,
+        borsh::BorshSerializer,
+        serde::JsonSerializer,
+        wincode::WincodeSerializer,
+    },
+    storage::Storage,
+};
+#[cfg(feature = "bincode")]
+use rust_generic_storage::serializer::bincode::BincodeSerializer;
+#[cfg(feature = "cbor")]
+use rust_generic_storage::serializer::cbor::CborSerializer;
+#[cfg(feature = "msgpack")]
+use rust_generic_storage::serializer::msgpack::MsgPackSerializer;
+#[cfg(feature = "postcard")]
+use rust_generic_storage::serializer::postcard::PostcardSerializer;
+
+const KB: usize = 1024;
+const MB: usize = 1024 * KB;
+const PAYLOAD_SIZES: [usize; 4] = [KB, 64 * KB, MB, 10 * MB];
+const LEDGER_SIZES: [usize; 3] = [10, 1_000, 10_000];
+const CONVERT_LEDGER_SIZE: usize = 1_000;
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, SchemaWrite, SchemaRead)]
+struct Blob {
+    id: u64,
+    data: Vec<u8>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, SchemaWrite, SchemaRead)]
+struct Account {
+    owner: [u8; 32],
+    lamports: u64,
+    delegate: Option<[u8; 32]>,
+    tags: Vec<String>,
+    history: Vec<Vec<u64>>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, SchemaWrite, SchemaRead)]
+struct Ledger {
+    slot: u64,
+    accounts: Vec<Account>,
+}
+
+fn make_blob(len: usize) -> Blob {
+    Blob {
+        id: len as u64,
+        data: (0..len).map(|i| (i % 251) as u8).collect(),
+    }
+}
+
+fn make_ledger(accounts: usize) -> Ledger {
+    Ledger {
+        slot: 250_000_000,
+        accounts: (0..accounts)
+            .map(|i| Account {
+                owner: [(i % 256) as u8; 32],
+                lamports: i as u64 * 1_000_000,
+                delegate: (i % 3 == 0).then_some([7; 32]),
+                tags: (0..i % 4).map(|tag| format!("tag-{tag}")).collect(),
+                history: (0..4).map(|epoch| vec![epoch, i as u64, epoch * i as u64]).collect(),
+            })
+            .collect(),
+    }
+}
+
+fn size_label(len: usize) -> String {
+    if len >= MB {
+        format!("{}MB", len / MB)
+    } else {
+        format!("{}KB", len / KB)
+    }
+}
+
+// Throughput is the encoded size for each format, so the reported bytes/sec
+// compare how fast each format moves its own output.
+fn bench_save_load<T, S: Serializer<T>>(
+    group: &mut BenchmarkGroup<'_, WallTime>,
+    name: &str,
+    parameter: &str,
+    serializer: S,
+    value: &T,
+) {
+    group.throughput(Throughput::Bytes(serializer.encoded_len(value).unwrap() as u64));
+    let mut storage = Storage::new(serializer);
+
+    group.bench_function(BenchmarkId::new(format!("{name}_save"), parameter), |b| {
+        b.iter(|| storage.save(black_box(value)).unwrap())
+    });
+    group.bench_function(BenchmarkId::new(format!("{name}_load"), parameter), |b| {
+        b.iter(|| black_box(storage.load().unwrap()))
+    });
+}
+
+fn bench_payload_sizes(c: &mut Criterion) {
+    let mut group = c.benchmark_group("payload_sizes");
+    group.sample_size(10);
+
+    for len in PAYLOAD_SIZES {
+        let blob = make_blob(len);
+        let label = size_label(len);
+
+        bench_save_load(&mut group, "borsh", &label, BorshSerializer, &blob);
+        bench_save_load(&mut group, "json", &label, JsonSerializer, &blob);
+        bench_save_load(&mut group, "wincode", &label, WincodeSerializer, &blob);
+        #[cfg(feature = "bincode")]
+        bench_save_load(&mut group, "bincode", &label, BincodeSerializer, &blob);
+        #[cfg(feature = "msgpack")]
+        bench_save_load(&mut group, "msgpack", &label, MsgPackSerializer, &blob);
+        #[cfg(feature = "cbor")]
+        bench_save_load(&mut group, "cbor", &label, CborSerializer, &blob);
+        #[cfg(feature = "postcard")]
+        bench_save_load(&mut group, "postcard", &label, PostcardSerializer, &blob);
+    }
+
+    group.finish();
+}
+
+fn bench_nested_collections(c: &mut Criterion) {
+    let mut group = c.benchmark_group("nested_collections");
+    group.sample_size(20);
+
+    for accounts in LEDGER_SIZES {
+        let ledger = make_ledger(accounts);
+        let label = format!("{accounts}_accounts");
+
+        bench_save_load(&mut group, "borsh", &label, BorshSerializer, &ledger);
+        bench_save_load(&mut group, "json", &label, JsonSerializer, &ledger);
+        bench_save_load(&mut group, "wincode", &label, WincodeSerializer, &ledger);
+        #[cfg(feature = "bincode")]
+        bench_save_load(&mut group, "bincode", &label, BincodeSerializer, &ledger);
+        #[cfg(feature = "msgpack")]
+        bench_save_load(&mut group, "msgpack", &label, MsgPackSerializer, &ledger);
+        #[cfg(feature = "cbor")]
+        bench_save_load(&mut group, "cbor", &label, CborSerializer, &ledger);
+        #[cfg(feature = "postcard")]
+        bench_save_load(&mut group, "postcard", &label, PostcardSerializer, &ledger);
+    }
+
+    group.finish();
+}
+
+fn bench_convert_pair<S: Serializer<Ledger>, S2: Serializer<Ledger>>(
+    group: &mut BenchmarkGroup<'_, WallTime>,
+    from: &str,
+    source: &impl Fn() -> S,
+    to: &str,
+    target: impl Fn() -> S2,
+    ledger: &Ledger,
+) {
+    group.throughput(Throughput::Bytes(source().encoded_len(ledger).unwrap() as u64));
+    group.bench_function(format!("{from}_to_{to}"), |b| {
+        b.iter_batched(
+            || {
+                let mut storage = Storage::new(source());
+                storage.save(ledger).unwrap();
+                (storage, target())
+            },
+            |(storage, target)| black_box(storage.convert(target).unwrap()),
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+fn bench_convert_from<S: Serializer<Ledger>>(
+    group: &mut BenchmarkGroup<'_, WallTime>,
+    from: &str,
+    source: impl Fn() -> S,
+    ledger: &Ledger,
+) {
+    bench_convert_pair(group, from, &source, "borsh", || BorshSerializer, ledger);
+    bench_convert_pair(group, from, &source, "json", || JsonSerializer, ledger);
+    bench_convert_pair(group, from, &source, "wincode", || WincodeSerializer, ledger);
+    #[cfg(feature = "bincode")]
+    bench_convert_pair(group, from, &source, "bincode", || BincodeSerializer, ledger);
+    #[cfg(feature = "msgpack")]
+    bench_convert_pair(group, from, &source, "msgpack", || MsgPackSerializer, ledger);
+    #[cfg(feature = "cbor")]
+    bench_convert_pair(group, from, &source, "cbor", || CborSerializer, ledger);
+    #[cfg(feature = "postcard")]
+    bench_convert_pair(group, from, &source, "postcard", || PostcardSerializer, ledger);
+}
+
+fn bench_convert_matrix(c: &mut Criterion) {
+    let ledger = make_ledger(CONVERT_LEDGER_SIZE);
+    let mut group = c.benchmark_group("convert_matrix");
+    group.sample_size(20);
+
+    bench_convert_from(&mut group, "borsh", || BorshSerializer, &ledger);
+    bench_convert_from(&mut group, "json", || JsonSerializer, &ledger);
+    bench_convert_from(&mut group, "wincode", || WincodeSerializer, &ledger);
+    #[cfg(feature = "bincode")]
+    bench_convert_from(&mut group, "bincode", || BincodeSerializer, &ledger);
+    #[cfg(feature = "msgpack")]
+    bench_convert_from(&mut group, "msgpack", || MsgPackSerializer, &ledger);
+    #[cfg(feature = "cbor")]
+    bench_convert_from(&mut group, "cbor", || CborSerializer, &ledger);
+    #[cfg(feature = "postcard")]
+    bench_convert_from(&mut group, "postcard", || PostcardSerializer, &ledger);
+
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_payload_sizes,
+    bench_nested_collections,
+    bench_convert_matrix,
+);
+criterion_main!(benches);
@@ -11,6 +11,27 @@ use rust_generic_storage::{
     },
     storage::Storage,
 };
+#[cfg(feature = "bincode")]
+use rust_generic_storage::serializer::bincode::BincodeSerializer;
+#[cfg(feature = "cbor")]
+use rust_generic_storage::serializer::cbor::CborSerializer;
+#[cfg(feature = "msgpack")]
+use rust_generic_storage::serializer::msgpack::MsgPackSerializer;
+#[cfg(feature = "postcard")]
+use rust_generic_storage::serializer::postcard::PostcardSerializer;
+#[cfg(any(feature = "zstd", feature = "lz4", feature = "deflate"))]
+use criterion::Throughput;
+#[cfg(any(feature = "zstd", feature = "lz4", feature = "deflate"))]
+use rust_generic_storage::serializer::{
+    Serializer,
+    compressed::{Compressed, Compression},
+};
+#[cfg(feature = "deflate")]
+use rust_generic_storage::serializer::compressed::Deflate;
+#[cfg(feature = "lz4")]
+use rust_generic_storage::serializer::compressed::Lz4;
+#[cfg(feature = "zstd")]
+use rust_generic_storage::serializer::compressed::Zstd;
 
 fn make_person() -> Person {
     Person {
@@ -140,6 +161,34 @@ fn bench_save_comparison(c: &mut Criterion) {
             s.save(black_box(&person)).unwrap();
         })
     });
+    #[cfg(feature = "bincode")]
+    group.bench_function("bincode", |b| {
+        b.iter(|| {
+            let mut s = Storage::new(BincodeSerializer);
+            s.save(black_box(&person)).unwrap();
+        })
+    });
+    #[cfg(feature = "msgpack")]
+    group.bench_function("msgpack", |b| {
+        b.iter(|| {
+            let mut s = Storage::new(MsgPackSerializer);
+            s.save(black_box(&person)).unwrap();
+        })
+    });
+    #[cfg(feature = "cbor")]
+    group.bench_function("cbor", |b| {
+        b.iter(|| {
+            let mut s = Storage::new(CborSerializer);
+            s.save(black_box(&person)).unwrap();
+        })
+    });
+    #[cfg(feature = "postcard")]
+    group.bench_function("postcard", |b| {
+        b.iter(|| {
+            let mut s = Storage::new(PostcardSerializer);
+            s.save(black_box(&person)).unwrap();
+        })
+    });
 
     group.finish();
 }
@@ -169,10 +218,130 @@ fn bench_roundtrip_comparison(c: &mut Criterion) {
             let _: Person = black_box(s.load().unwrap());
         })
     });
+    #[cfg(feature = "bincode")]
+    group.bench_function("bincode", |b| {
+        b.iter(|| {
+            let mut s = Storage::new(BincodeSerializer);
+            s.save(black_box(&person)).unwrap();
+            let _: Person = black_box(s.load().unwrap());
+        })
+    });
+    #[cfg(feature = "msgpack")]
+    group.bench_function("msgpack", |b| {
+        b.iter(|| {
+            let mut s = Storage::new(MsgPackSerializer);
+            s.save(black_box(&person)).unwrap();
+            let _: Person = black_box(s.load().unwrap());
+        })
+    });
+    #[cfg(feature = "cbor")]
+    group.bench_function("cbor", |b| {
+        b.iter(|| {
+            let mut s = Storage::new(CborSerializer);
+            s.save(black_box(&person)).unwrap();
+            let _: Person = black_box(s.load().unwrap());
+        })
+    });
+    #[cfg(feature = "postcard")]
+    group.bench_function("postcard", |b| {
+        b.iter(|| {
+            let mut s = Storage::new(PostcardSerializer);
+            s.save(black_box(&person)).unwrap();
+            let _: Person = black_box(s.load().unwrap());
+        })
+    });
+
+    group.finish();
+}
+
+fn bench_view_comparison(c: &mut Criterion) {
+    let person = Person {
+        name: "Alice".repeat(1024),
+        age: 30,
+    };
+    let mut group = c.benchmark_group("view_comparison");
+
+    let mut json = Storage::new(JsonSerializer);
+    json.save(&person).unwrap();
+    group.bench_function("json_load", |b| {
+        b.iter(|| {
+            let _: Person = black_box(json.load().unwrap());
+        })
+    });
+    group.bench_function("json_view", |b| {
+        b.iter(|| black_box(json.with_view(|view| view.name.len()).unwrap()))
+    });
+
+    let mut wincode = Storage::new(WincodeSerializer);
+    wincode.save(&person).unwrap();
+    group.bench_function("wincode_load", |b| {
+        b.iter(|| {
+            let _: Person = black_box(wincode.load().unwrap());
+        })
+    });
+    group.bench_function("wincode_view", |b| {
+        b.iter(|| black_box(wincode.with_view(|view| view.name.len()).unwrap()))
+    });
 
     group.finish();
 }
 
+#[cfg(any(feature = "zstd", feature = "lz4", feature = "deflate"))]
+fn bench_compressed<S, C>(
+    group: &mut criterion::BenchmarkGroup<'_, criterion::measurement::WallTime>,
+    name: &str,
+    serializer: Compressed<S, C>,
+    person: &Person,
+) where
+    S: Serializer<Person>,
+    C: Compression,
+{
+    // Reported per compressed byte, so the formats' output sizes show up
+    // next to their timings.
+    group.throughput(Throughput::Bytes(serializer.encoded_len(person).unwrap() as u64));
+    let mut storage = Storage::new(serializer);
+
+    group.bench_function(name, |b| {
+        b.iter(|| {
+            storage.save(black_box(person)).unwrap();
+            let _: Person = black_box(storage.load().unwrap());
+        })
+    });
+}
+
+#[cfg(any(feature = "zstd", feature = "lz4", feature = "deflate"))]
+fn bench_compression_comparison(c: &mut Criterion) {
+    let person = Person {
+        name: "Alice".repeat(1024),
+        age: 30,
+    };
+    let mut group = c.benchmark_group("compression_comparison");
+
+    #[cfg(feature = "zstd")]
+    {
+        bench_compressed(&mut group, "borsh_zstd", Compressed::new(BorshSerializer, Zstd::default()), &person);
+        bench_compressed(&mut group, "json_zstd", Compressed::new(JsonSerializer, Zstd::default()), &person);
+        bench_compressed(&mut group, "wincode_zstd", Compressed::new(WincodeSerializer, Zstd::default()), &person);
+    }
+    #[cfg(feature = "lz4")]
+    {
+        bench_compressed(&mut group, "borsh_lz4", Compressed::new(BorshSerializer, Lz4), &person);
+        bench_compressed(&mut group, "json_lz4", Compressed::new(JsonSerializer, Lz4), &person);
+        bench_compressed(&mut group, "wincode_lz4", Compressed::new(WincodeSerializer, Lz4), &person);
+    }
+    #[cfg(feature = "deflate")]
+    {
+        bench_compressed(&mut group, "borsh_deflate", Compressed::new(BorshSerializer, Deflate::default()), &person);
+        bench_compressed(&mut group, "json_deflate", Compressed::new(JsonSerializer, Deflate::default()), &person);
+        bench_compressed(&mut group, "wincode_deflate", Compressed::new(WincodeSerializer, Deflate::default()), &person);
+    }
+
+    group.finish();
+}
+
+#[cfg(not(any(feature = "zstd", feature = "lz4", feature = "deflate")))]
+fn bench_compression_comparison(_: &mut Criterion) {}
+
 criterion_group!(
     benches,
     bench_borsh_save,
@@ -186,5 +355,7 @@ criterion_group!(
     bench_wincode_roundtrip,
     bench_save_comparison,
     bench_roundtrip_comparison,
+    bench_view_comparison,
+    bench_compression_comparison,
 );
 criterion_main!(benches);
\ No newline at end of file
@@ -0,0 +1,83 @@
+use std::path::Path;
+
+use crate::{
+    backend::{memory::MemoryBackend, tokio_file::TokioFileBackend, AsyncStorageBackend},
+    error::StorageError,
+    migration::{Migrate, MigrateFn},
+    serializer::Serializer,
+    storage::{decode, encode},
+};
+
+// Async version of `Storage` for tokio. Encoding and decoding go through the
+// same `Serializer<T>` implementations and run inline, only the backend IO is
+// awaited.
+pub struct AsyncStorage<T, S, B = MemoryBackend> {
+    backend: B,
+    serializer: S,
+    envelope: Option<u16>,
+    migrate: Option<MigrateFn<S>>,
+    _marker: std::marker::PhantomData<T>,
+}
+
+impl<T, S: Serializer<T>> AsyncStorage<T, S> {
+    pub fn new(serializer: S) -> Self {
+        Self::with_backend(serializer, MemoryBackend::new())
+    }
+}
+
+impl<T, S: Serializer<T>> AsyncStorage<T, S, TokioFileBackend> {
+    pub fn open(path: impl AsRef<Path>, serializer: S) -> Self {
+        Self::with_backend(serializer, TokioFileBackend::new(path))
+    }
+}
+
+impl<T, S: Serializer<T>, B: AsyncStorageBackend> AsyncStorage<T, S, B> {
+    pub fn with_backend(serializer: S, backend: B) -> Self {
+        Self {
+            backend,
+            serializer,
+            envelope: None,
+            migrate: None,
+            _marker: std::marker::PhantomData,
+        }
+    }
+
+    // See `Storage::with_envelope`; blobs are interchangeable between the two.
+    pub fn with_envelope(mut self, schema_version: u16) -> Self {
+        self.envelope = Some(schema_version);
+        self
+    }
+
+    // See `Storage::with_migrations`; older blobs are upgraded on `load`.
+    pub fn with_migrations(mut self) -> Self
+    where
+        T: Migrate<S>,
+    {
+        self.envelope = Some(T::SCHEMA_VERSION);
+        self.migrate = Some(T::migrate_from);
+        self
+    }
+
+    pub async fn save(&mut self, value: &T) -> Result<(), StorageError> {
+        let bytes = encode(&self.serializer, self.envelope, value)?;
+        self.backend.write(&bytes).await?;
+        Ok(())
+    }
+
+    pub async fn load(&self) -> Result<T, StorageError> {
+        let bytes = self.backend.read().await?.ok_or(StorageError::Empty)?;
+        decode(&self.serializer, self.envelope, self.migrate, &bytes)
+    }
+
+    pub async fn has_data(&self) -> bool {
+        self.backend.exists().await
+    }
+
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    pub fn into_backend(self) -> B {
+        self.backend
+    }
+}
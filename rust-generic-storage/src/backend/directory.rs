@@ -0,0 +1,97 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::{read_optional, write_atomic, StorageBackend};
+
+const SNAPSHOT_EXTENSION: &str = "bin";
+
+// Every save lands in its own numbered file (`00000000000000000001.bin`, ...)
+// and `read` returns the highest-numbered one. `retain` bounds how many
+// snapshots are kept on disk; by default all of them are.
+pub struct DirectoryBackend {
+    dir: PathBuf,
+    retain: Option<usize>,
+}
+
+impl DirectoryBackend {
+    pub fn new(dir: impl AsRef<Path>) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+            retain: None,
+        }
+    }
+
+    pub fn retain(mut self, count: usize) -> Self {
+        self.retain = Some(count.max(1));
+        self
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn snapshots(&self) -> std::io::Result<Vec<PathBuf>> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err),
+        };
+
+        let mut snapshots = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if Self::sequence_of(&path).is_some() {
+                snapshots.push(path);
+            }
+        }
+        snapshots.sort_by_key(|path| Self::sequence_of(path));
+        Ok(snapshots)
+    }
+
+    fn sequence_of(path: &Path) -> Option<u64> {
+        if path.extension()? != SNAPSHOT_EXTENSION {
+            return None;
+        }
+        path.file_stem()?.to_str()?.parse().ok()
+    }
+
+    fn snapshot_path(&self, sequence: u64) -> PathBuf {
+        self.dir
+            .join(format!("{sequence:020}"))
+            .with_extension(SNAPSHOT_EXTENSION)
+    }
+}
+
+impl StorageBackend for DirectoryBackend {
+    fn read(&self) -> std::io::Result<Option<Vec<u8>>> {
+        match self.snapshots()?.last() {
+            Some(path) => read_optional(path),
+            None => Ok(None),
+        }
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<()> {
+        let snapshots = self.snapshots()?;
+        let next = snapshots
+            .last()
+            .and_then(|path| Self::sequence_of(path))
+            .map_or(1, |sequence| sequence + 1);
+
+        write_atomic(&self.snapshot_path(next), bytes)?;
+
+        if let Some(retain) = self.retain {
+            // `snapshots` does not include the file just written.
+            let stale = (snapshots.len() + 1).saturating_sub(retain);
+            for path in snapshots.iter().take(stale) {
+                fs::remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn exists(&self) -> bool {
+        self.snapshots().is_ok_and(|snapshots| !snapshots.is_empty())
+    }
+}
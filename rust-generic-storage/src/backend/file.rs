@@ -0,0 +1,35 @@
+use std::path::{Path, PathBuf};
+
+use super::{read_optional, write_atomic, StorageBackend};
+
+pub struct FileBackend {
+    path: PathBuf,
+}
+
+impl FileBackend {
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl StorageBackend for FileBackend {
+    fn read(&self) -> std::io::Result<Option<Vec<u8>>> {
+        read_optional(&self.path)
+    }
+
+    // Written to a sibling `.tmp` file first and renamed over the target,
+    // so a crash mid-save never leaves a half-written value behind.
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<()> {
+        write_atomic(&self.path, bytes)
+    }
+
+    fn exists(&self) -> bool {
+        self.path.is_file()
+    }
+}
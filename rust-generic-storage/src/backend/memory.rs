@@ -0,0 +1,47 @@
+use super::StorageBackend;
+
+#[derive(Default)]
+pub struct MemoryBackend {
+    data: Option<Vec<u8>>,
+}
+
+impl MemoryBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl StorageBackend for MemoryBackend {
+    fn read(&self) -> std::io::Result<Option<Vec<u8>>> {
+        Ok(self.data.clone())
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<()> {
+        self.data = Some(bytes.to_vec());
+        Ok(())
+    }
+
+    fn exists(&self) -> bool {
+        self.data.is_some()
+    }
+
+    fn read_with<R>(&self, f: impl FnOnce(&[u8]) -> R) -> std::io::Result<Option<R>> {
+        Ok(self.data.as_deref().map(f))
+    }
+}
+
+#[cfg(feature = "async")]
+impl super::AsyncStorageBackend for MemoryBackend {
+    async fn read(&self) -> std::io::Result<Option<Vec<u8>>> {
+        Ok(self.data.clone())
+    }
+
+    async fn write(&mut self, bytes: &[u8]) -> std::io::Result<()> {
+        self.data = Some(bytes.to_vec());
+        Ok(())
+    }
+
+    async fn exists(&self) -> bool {
+        self.data.is_some()
+    }
+}
@@ -0,0 +1,56 @@
+pub mod directory;
+pub mod file;
+pub mod memory;
+pub mod wal;
+#[cfg(feature = "async")]
+pub mod tokio_file;
+
+use std::{fs, io::Write, path::Path};
+
+pub trait StorageBackend {
+    fn read(&self) -> std::io::Result<Option<Vec<u8>>>;
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<()>;
+    fn exists(&self) -> bool;
+
+    // Lends the stored bytes to `f` instead of returning a copy. Backends
+    // that already hold the bytes in memory should override this.
+    fn read_with<R>(&self, f: impl FnOnce(&[u8]) -> R) -> std::io::Result<Option<R>> {
+        Ok(self.read()?.map(|bytes| f(&bytes)))
+    }
+}
+
+// Async counterpart of `StorageBackend` for use under tokio. The futures are
+// `Send` so `AsyncStorage` can be driven from spawned tasks.
+#[cfg(feature = "async")]
+pub trait AsyncStorageBackend {
+    fn read(&self) -> impl Future<Output = std::io::Result<Option<Vec<u8>>>> + Send;
+    fn write(&mut self, bytes: &[u8]) -> impl Future<Output = std::io::Result<()>> + Send;
+    fn exists(&self) -> impl Future<Output = bool> + Send;
+}
+
+pub(crate) fn write_atomic(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    if let Some(parent) = path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut tmp_name = path.as_os_str().to_owned();
+    tmp_name.push(".tmp");
+    let tmp_path = Path::new(&tmp_name);
+
+    let mut file = fs::File::create(tmp_path)?;
+    file.write_all(bytes)?;
+    file.sync_all()?;
+    drop(file);
+
+    fs::rename(tmp_path, path)
+}
+
+pub(crate) fn read_optional(path: &Path) -> std::io::Result<Option<Vec<u8>>> {
+    match fs::read(path) {
+        Ok(bytes) => Ok(Some(bytes)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
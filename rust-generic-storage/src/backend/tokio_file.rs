@@ -0,0 +1,57 @@
+use std::path::{Path, PathBuf};
+
+use tokio::{fs, io::AsyncWriteExt};
+
+use super::AsyncStorageBackend;
+
+pub struct TokioFileBackend {
+    path: PathBuf,
+}
+
+impl TokioFileBackend {
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl AsyncStorageBackend for TokioFileBackend {
+    async fn read(&self) -> std::io::Result<Option<Vec<u8>>> {
+        match fs::read(&self.path).await {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    // Same `.tmp` + rename scheme as `FileBackend::write`.
+    async fn write(&mut self, bytes: &[u8]) -> std::io::Result<()> {
+        if let Some(parent) = self.path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            fs::create_dir_all(parent).await?;
+        }
+
+        let mut tmp_name = self.path.as_os_str().to_owned();
+        tmp_name.push(".tmp");
+        let tmp_path = PathBuf::from(tmp_name);
+
+        let mut file = fs::File::create(&tmp_path).await?;
+        file.write_all(bytes).await?;
+        file.sync_all().await?;
+        drop(file);
+
+        fs::rename(&tmp_path, &self.path).await
+    }
+
+    async fn exists(&self) -> bool {
+        fs::metadata(&self.path)
+            .await
+            .is_ok_and(|metadata| metadata.is_file())
+    }
+}
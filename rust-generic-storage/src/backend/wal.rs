@@ -0,0 +1,178 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
+
+use super::{write_atomic, StorageBackend};
+
+const RECORD_HEADER_LEN: usize = 4 + 4;
+
+// Append-only log of saved values. Each record is laid out as
+// payload len [4, LE] | crc32 [4, LE] | payload
+// and `open` replays the log up to the last record that is complete and
+// passes its checksum, truncating whatever a crash left after it. The log is
+// rewritten down to the current value every `compact_every` records.
+pub struct WalBackend {
+    path: PathBuf,
+    file: File,
+    current: Option<Vec<u8>>,
+    records: usize,
+    // Length of the log up to the end of the last committed record.
+    len: u64,
+    compact_every: Option<usize>,
+    #[cfg(test)]
+    fail_next_append: Option<usize>,
+}
+
+impl WalBackend {
+    pub fn open(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(parent) = path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut file = Self::open_log(&path)?;
+        let mut log = Vec::new();
+        file.read_to_end(&mut log)?;
+
+        let (current, records, valid_len) = replay(&log);
+        if valid_len < log.len() {
+            file.set_len(valid_len as u64)?;
+            file.sync_all()?;
+        }
+
+        Ok(Self {
+            path,
+            file,
+            current,
+            records,
+            len: valid_len as u64,
+            compact_every: None,
+            #[cfg(test)]
+            fail_next_append: None,
+        })
+    }
+
+    pub fn compact_every(mut self, records: usize) -> Self {
+        self.compact_every = Some(records.max(1));
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn records(&self) -> usize {
+        self.records
+    }
+
+    // Replaces the log with a single record holding the current value.
+    pub fn compact(&mut self) -> std::io::Result<()> {
+        let log = match &self.current {
+            Some(bytes) => encode_record(bytes)?,
+            None => Vec::new(),
+        };
+        write_atomic(&self.path, &log)?;
+        self.file = Self::open_log(&self.path)?;
+        self.records = usize::from(self.current.is_some());
+        self.len = log.len() as u64;
+        Ok(())
+    }
+
+    // Makes the next append write only `written` bytes of its record and
+    // then fail, like a write cut short by a full disk.
+    #[cfg(test)]
+    pub(crate) fn fail_next_append(&mut self, written: usize) {
+        self.fail_next_append = Some(written);
+    }
+
+    fn append(&mut self, record: &[u8]) -> std::io::Result<()> {
+        #[cfg(test)]
+        if let Some(written) = self.fail_next_append.take() {
+            self.file.write_all(&record[..written.min(record.len())])?;
+            return Err(std::io::Error::other("injected wal append failure"));
+        }
+
+        self.file.write_all(record)?;
+        self.file.sync_data()
+    }
+
+    fn open_log(path: &Path) -> std::io::Result<File> {
+        OpenOptions::new().read(true).append(true).create(true).open(path)
+    }
+}
+
+impl StorageBackend for WalBackend {
+    fn read(&self) -> std::io::Result<Option<Vec<u8>>> {
+        Ok(self.current.clone())
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<()> {
+        // A failed append may leave part of the record behind. Replay would
+        // stop there and drop every later save, so the log is cut back to
+        // the last committed record. The file is opened for appending, so
+        // the next write lands at the new end without a seek.
+        let record = encode_record(bytes)?;
+        if let Err(err) = self.append(&record) {
+            self.file.set_len(self.len)?;
+            return Err(err);
+        }
+        self.len += record.len() as u64;
+        self.current = Some(bytes.to_vec());
+        self.records += 1;
+
+        if self.compact_every.is_some_and(|every| self.records >= every) {
+            self.compact()?;
+        }
+        Ok(())
+    }
+
+    fn exists(&self) -> bool {
+        self.current.is_some()
+    }
+
+    fn read_with<R>(&self, f: impl FnOnce(&[u8]) -> R) -> std::io::Result<Option<R>> {
+        Ok(self.current.as_deref().map(f))
+    }
+}
+
+fn encode_record(payload: &[u8]) -> std::io::Result<Vec<u8>> {
+    let len = u32::try_from(payload.len()).map_err(|_| {
+        std::io::Error::new(std::io::ErrorKind::InvalidInput, "payload too large for wal record")
+    })?;
+
+    let mut record = Vec::with_capacity(RECORD_HEADER_LEN + payload.len());
+    record.extend_from_slice(&len.to_le_bytes());
+    record.extend_from_slice(&crc32fast::hash(payload).to_le_bytes());
+    record.extend_from_slice(payload);
+    Ok(record)
+}
+
+// Returns the last valid payload, how many valid records precede the first
+// bad one, and the byte length of that valid prefix.
+fn replay(log: &[u8]) -> (Option<Vec<u8>>, usize, usize) {
+    let mut offset = 0;
+    let mut records = 0;
+    let mut current = None;
+
+    while let Some(header) = log.get(offset..offset + RECORD_HEADER_LEN) {
+        let len = u32::from_le_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        let crc = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+        let start = offset + RECORD_HEADER_LEN;
+        let Some(payload) = log.get(start..start + len) else {
+            break;
+        };
+        if crc32fast::hash(payload) != crc {
+            break;
+        }
+
+        current = Some(payload);
+        records += 1;
+        offset = start + len;
+    }
+
+    (current.map(<[u8]>::to_vec), records, offset)
+}
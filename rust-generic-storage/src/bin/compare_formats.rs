@@ -0,0 +1,19 @@
+use rust_generic_storage::{person::Person, report::compare_formats};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let people = [
+        Person { name: "Alice".to_string(), age: 30 },
+        Person { name: "Alice 🦀".to_string(), age: u32::MAX },
+        Person { name: "A".repeat(256), age: 0 },
+    ];
+
+    for person in &people {
+        println!("{person:?}");
+        println!("  {:<10} {:>8}  canonical", "format", "bytes");
+        for report in compare_formats(person)? {
+            println!("  {:<10} {:>8}  {}", report.format, report.encoded_len, report.canonical);
+        }
+        println!();
+    }
+    Ok(())
+}
@@ -0,0 +1,59 @@
+use crate::error::StorageError;
+
+pub const MAGIC: [u8; 4] = *b"RGSE";
+pub const HEADER_LEN: usize = 4 + 1 + 2 + 4 + 4;
+
+// Layout (little endian):
+// magic [4] | format id [1] | schema version [2] | payload len [4] | crc32 [4] | payload
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Header {
+    pub format_id: u8,
+    pub schema_version: u16,
+    pub len: u32,
+    pub crc: u32,
+}
+
+pub fn is_enveloped(bytes: &[u8]) -> bool {
+    bytes.len() >= HEADER_LEN && bytes[..4] == MAGIC
+}
+
+pub fn wrap(format_id: u8, schema_version: u16, payload: &[u8]) -> Result<Vec<u8>, StorageError> {
+    let len = u32::try_from(payload.len())
+        .map_err(|_| StorageError::Integrity("payload too large for envelope".to_string()))?;
+
+    let mut bytes = Vec::with_capacity(HEADER_LEN + payload.len());
+    bytes.extend_from_slice(&MAGIC);
+    bytes.push(format_id);
+    bytes.extend_from_slice(&schema_version.to_le_bytes());
+    bytes.extend_from_slice(&len.to_le_bytes());
+    bytes.extend_from_slice(&crc32fast::hash(payload).to_le_bytes());
+    bytes.extend_from_slice(payload);
+    Ok(bytes)
+}
+
+pub fn unwrap(bytes: &[u8]) -> Result<(Header, &[u8]), StorageError> {
+    if !is_enveloped(bytes) {
+        return Err(StorageError::Integrity("missing envelope header".to_string()));
+    }
+
+    let header = Header {
+        format_id: bytes[4],
+        schema_version: u16::from_le_bytes([bytes[5], bytes[6]]),
+        len: u32::from_le_bytes([bytes[7], bytes[8], bytes[9], bytes[10]]),
+        crc: u32::from_le_bytes([bytes[11], bytes[12], bytes[13], bytes[14]]),
+    };
+
+    let payload = &bytes[HEADER_LEN..];
+    if payload.len() != header.len as usize {
+        return Err(StorageError::Integrity(format!(
+            "envelope declares {} payload bytes, found {}",
+            header.len,
+            payload.len()
+        )));
+    }
+    if crc32fast::hash(payload) != header.crc {
+        return Err(StorageError::Integrity("envelope checksum mismatch".to_string()));
+    }
+
+    Ok((header, payload))
+}
@@ -0,0 +1,105 @@
+use std::fmt;
+
+type Source = Box<dyn std::error::Error + Send + Sync>;
+
+#[derive(Debug)]
+pub enum StorageError {
+    Empty,
+    Encode { format: &'static str, source: Source },
+    Decode { format: &'static str, offset: Option<usize>, source: Source },
+    Io(std::io::Error),
+    Integrity(String),
+    Tampered,
+    FormatMismatch { expected: u8, found: u8 },
+    UnknownFormat { format_id: u8 },
+    SchemaVersion { found: u16, current: u16 },
+    NoMigration { from: u16 },
+    VersionNotFound { version: usize },
+}
+
+impl StorageError {
+    pub fn encode(format: &'static str, source: impl Into<Source>) -> Self {
+        Self::Encode { format, source: source.into() }
+    }
+
+    pub fn decode(format: &'static str, source: impl Into<Source>) -> Self {
+        Self::Decode { format, offset: None, source: source.into() }
+    }
+
+    pub fn decode_at(format: &'static str, offset: usize, source: impl Into<Source>) -> Self {
+        Self::Decode { format, offset: Some(offset), source: source.into() }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        matches!(self, Self::Empty)
+    }
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "no data to load"),
+            Self::Encode { format, source } => write!(f, "failed to encode {format}: {source}"),
+            Self::Decode { format, offset: Some(offset), source } => {
+                write!(f, "failed to decode {format} at byte {offset}: {source}")
+            }
+            Self::Decode { format, offset: None, source } => {
+                write!(f, "failed to decode {format}: {source}")
+            }
+            Self::Io(err) => write!(f, "io error: {err}"),
+            Self::Integrity(reason) => write!(f, "integrity check failed: {reason}"),
+            Self::Tampered => write!(f, "authentication failed, data was modified or the key is wrong"),
+            Self::FormatMismatch { expected, found } => {
+                write!(f, "stored with format {found}, expected format {expected}")
+            }
+            Self::UnknownFormat { format_id } => write!(f, "unknown format id {format_id}"),
+            Self::SchemaVersion { found, current } => {
+                write!(f, "stored schema version {found} does not match current version {current}")
+            }
+            Self::NoMigration { from } => write!(f, "no migration from schema version {from}"),
+            Self::VersionNotFound { version } => write!(f, "no version {version} in history"),
+        }
+    }
+}
+
+impl std::error::Error for StorageError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Encode { source, .. } | Self::Decode { source, .. } => Some(source.as_ref()),
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+// borsh reports both its own failures and real IO failures as
+// `std::io::Error`, so this impl cannot tell them apart. `BorshSerializer`
+// maps its errors explicitly instead of relying on `?`.
+impl From<std::io::Error> for StorageError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for StorageError {
+    fn from(err: serde_json::Error) -> Self {
+        if err.is_io() {
+            return Self::Io(err.into());
+        }
+        // `to_vec` writes a single line, so the column is the byte offset.
+        let offset = (err.line() == 1).then(|| err.column().saturating_sub(1));
+        Self::Decode { format: "json", offset, source: Box::new(err) }
+    }
+}
+
+impl From<wincode::ReadError> for StorageError {
+    fn from(err: wincode::ReadError) -> Self {
+        Self::decode("wincode", err)
+    }
+}
+
+impl From<wincode::WriteError> for StorageError {
+    fn from(err: wincode::WriteError) -> Self {
+        Self::encode("wincode", err)
+    }
+}
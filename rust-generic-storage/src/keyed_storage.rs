@@ -0,0 +1,94 @@
+use std::collections::BTreeMap;
+
+use crate::{error::StorageError, serializer::Serializer};
+
+// Keys are stored in their encoded form, so iteration order follows the
+// key serializer's byte order rather than `K`'s own ordering.
+pub struct KeyedStorage<K, T, S, KS = S> {
+    entries: BTreeMap<Vec<u8>, Vec<u8>>,
+    serializer: S,
+    key_serializer: KS,
+    _marker: std::marker::PhantomData<(K, T)>,
+}
+
+impl<K, T, S: Serializer<T>, KS: Serializer<K>> KeyedStorage<K, T, S, KS> {
+    pub fn new(serializer: S, key_serializer: KS) -> Self {
+        Self {
+            entries: BTreeMap::new(),
+            serializer,
+            key_serializer,
+            _marker: std::marker::PhantomData,
+        }
+    }
+
+    pub fn insert(&mut self, key: &K, value: &T) -> Result<Option<T>, StorageError> {
+        let key_bytes = self.key_serializer.to_bytes(key)?;
+        let value_bytes = self.serializer.to_bytes(value)?;
+        match self.entries.insert(key_bytes, value_bytes) {
+            Some(previous) => Ok(Some(self.serializer.from_bytes(&previous)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Result<Option<T>, StorageError> {
+        let key_bytes = self.key_serializer.to_bytes(key)?;
+        match self.entries.get(&key_bytes) {
+            Some(bytes) => Ok(Some(self.serializer.from_bytes(bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn remove(&mut self, key: &K) -> Result<Option<T>, StorageError> {
+        let key_bytes = self.key_serializer.to_bytes(key)?;
+        match self.entries.remove(&key_bytes) {
+            Some(bytes) => Ok(Some(self.serializer.from_bytes(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn contains_key(&self, key: &K) -> Result<bool, StorageError> {
+        let key_bytes = self.key_serializer.to_bytes(key)?;
+        Ok(self.entries.contains_key(&key_bytes))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Result<(K, T), StorageError>> + '_ {
+        self.entries.iter().map(|(key_bytes, value_bytes)| {
+            let key = self.key_serializer.from_bytes(key_bytes)?;
+            let value = self.serializer.from_bytes(value_bytes)?;
+            Ok((key, value))
+        })
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = Result<K, StorageError>> + '_ {
+        self.entries
+            .keys()
+            .map(|key_bytes| self.key_serializer.from_bytes(key_bytes))
+    }
+
+    pub fn convert<S2: Serializer<T>>(self, new_serializer: S2) -> Result<KeyedStorage<K, T, S2, KS>, StorageError> {
+        let mut entries = BTreeMap::new();
+        for (key_bytes, value_bytes) in self.entries {
+            let value = self.serializer.from_bytes(&value_bytes)?;
+            entries.insert(key_bytes, new_serializer.to_bytes(&value)?);
+        }
+
+        Ok(KeyedStorage {
+            entries,
+            serializer: new_serializer,
+            key_serializer: self.key_serializer,
+            _marker: std::marker::PhantomData,
+        })
+    }
+}
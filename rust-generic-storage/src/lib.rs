@@ -1,18 +1,60 @@
+#[cfg(feature = "async")]
+pub mod async_storage;
+pub mod backend;
+pub mod envelope;
+pub mod error;
+pub mod keyed_storage;
+pub mod migration;
 pub mod serializer;
+pub mod shared_storage;
 pub mod storage;
+mod test_suite;
 pub mod person;
+pub mod report;
 
 #[cfg(test)]
 mod tests {
     use crate::{
-        person::Person,
+        backend::{directory::DirectoryBackend, file::FileBackend, wal::WalBackend, StorageBackend},
+        envelope,
+        error::StorageError,
+        keyed_storage::KeyedStorage,
+        migration::Migrate,
+        person::{Person, PersonView},
+        report::compare_formats,
         serializer::{
+            Serializer,
             borsh::BorshSerializer,
             serde::JsonSerializer,
             wincode::WincodeSerializer,
         },
+        shared_storage::SharedStorage,
         storage::Storage,
     };
+    #[cfg(feature = "bincode")]
+    use crate::serializer::bincode::BincodeSerializer;
+    #[cfg(feature = "cbor")]
+    use crate::serializer::cbor::CborSerializer;
+    #[cfg(feature = "msgpack")]
+    use crate::serializer::msgpack::MsgPackSerializer;
+    #[cfg(feature = "postcard")]
+    use crate::serializer::postcard::PostcardSerializer;
+    #[cfg(feature = "deflate")]
+    use crate::serializer::compressed::Deflate;
+    #[cfg(feature = "lz4")]
+    use crate::serializer::compressed::Lz4;
+    #[cfg(feature = "zstd")]
+    use crate::serializer::compressed::Zstd;
+    #[cfg(any(feature = "zstd", feature = "lz4", feature = "deflate"))]
+    use crate::serializer::compressed::{Compressed, Compression};
+    #[cfg(feature = "encryption")]
+    use crate::serializer::encrypted::Encrypted;
+    #[cfg(feature = "async")]
+    use crate::async_storage::AsyncStorage;
+    #[cfg(feature = "anchor")]
+    use crate::serializer::anchor::AnchorAccountSerializer;
+    #[cfg(feature = "pod")]
+    use crate::serializer::pod::PodSerializer;
 
     type TestResult = Result<(), Box<dyn std::error::Error>>;
 
@@ -23,344 +65,1418 @@ mod tests {
         }
     }
 
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "rust-generic-storage-{}-{}",
+            std::process::id(),
+            name
+        ));
+        let _ = std::fs::remove_dir_all(&path);
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
     #[test]
-    fn borsh_roundtrip() -> TestResult {
-        let person = make_person();
+    fn borsh_has_data_after_save() -> TestResult {
         let mut storage = Storage::new(BorshSerializer);
-        storage.save(&person)?;
-        let loaded = storage.load()?;
-        assert_eq!(person, loaded);
+        assert!(!storage.has_data());
+        storage.save(&make_person())?;
+        assert!(storage.has_data());
         Ok(())
     }
 
     #[test]
-    fn borsh_name_preserved() -> TestResult {
-        let person = make_person();
-        let mut storage = Storage::new(BorshSerializer);
-        storage.save(&person)?;
-        let loaded: Person = storage.load()?;
-        assert_eq!(loaded.name, "Alice");
+    fn json_has_data_after_save() -> TestResult {
+        let mut storage = Storage::new(JsonSerializer);
+        assert!(!storage.has_data());
+        storage.save(&make_person())?;
+        assert!(storage.has_data());
         Ok(())
     }
 
     #[test]
-    fn borsh_age_preserved() -> TestResult {
-        let person = make_person();
-        let mut storage = Storage::new(BorshSerializer);
-        storage.save(&person)?;
-        let loaded: Person = storage.load()?;
-        assert_eq!(loaded.age, 30);
+    fn wincode_has_data_after_save() -> TestResult {
+        let mut storage = Storage::new(WincodeSerializer);
+        assert!(!storage.has_data());
+        storage.save(&make_person())?;
+        assert!(storage.has_data());
         Ok(())
     }
 
     #[test]
-    fn borsh_empty_name() -> TestResult {
-        let person = Person { name: "".to_string(), age: 0 };
+    fn borsh_load_without_save_returns_error() {
+        let storage: Storage<Person, _> = Storage::new(BorshSerializer);
+        assert!(storage.load().is_err());
+    }
+
+    #[test]
+    fn json_load_without_save_returns_error() {
+        let storage: Storage<Person, _> = Storage::new(JsonSerializer);
+        assert!(storage.load().is_err());
+    }
+
+    #[test]
+    fn wincode_load_without_save_returns_error() {
+        let storage: Storage<Person, _> = Storage::new(WincodeSerializer);
+        assert!(storage.load().is_err());
+    }
+
+    #[test]
+    fn convert_preserves_has_data() -> TestResult {
+        let person = make_person();
         let mut storage = Storage::new(BorshSerializer);
         storage.save(&person)?;
-        let loaded: Person = storage.load()?;
-        assert_eq!(loaded, person);
+        let converted = storage.convert(JsonSerializer)?;
+        assert!(converted.has_data());
         Ok(())
     }
 
     #[test]
-    fn borsh_unicode_name() -> TestResult {
-        let person = Person { name: "Alice 🦀".to_string(), age: 99 };
+    fn convert_without_save_returns_error() {
+        let storage: Storage<Person, _> = Storage::new(BorshSerializer);
+        assert!(storage.convert(JsonSerializer).is_err());
+    }
+
+    #[test]
+    fn convert_chain_borsh_json_wincode() -> TestResult {
+        let person = make_person();
         let mut storage = Storage::new(BorshSerializer);
         storage.save(&person)?;
-        let loaded: Person = storage.load()?;
+        let loaded: Person = storage
+            .convert(JsonSerializer)?
+            .convert(WincodeSerializer)?
+            .load()?;
         assert_eq!(loaded, person);
         Ok(())
     }
 
     #[test]
-    fn borsh_max_age() -> TestResult {
-        let person = Person { name: "Bob".to_string(), age: u32::MAX };
-        let mut storage = Storage::new(BorshSerializer);
-        storage.save(&person)?;
-        let loaded: Person = storage.load()?;
-        assert_eq!(loaded.age, u32::MAX);
+    fn all_serializers_same_result() -> TestResult {
+        let person = make_person();
+
+        let mut s1 = Storage::new(BorshSerializer);
+        s1.save(&person)?;
+        let r1: Person = s1.load()?;
+
+        let mut s2 = Storage::new(JsonSerializer);
+        s2.save(&person)?;
+        let r2: Person = s2.load()?;
+
+        let mut s3 = Storage::new(WincodeSerializer);
+        s3.save(&person)?;
+        let r3: Person = s3.load()?;
+
+        assert_eq!(r1, r2);
+        assert_eq!(r2, r3);
         Ok(())
     }
 
     #[test]
-    fn borsh_has_data_after_save() -> TestResult {
-        let mut storage = Storage::new(BorshSerializer);
+    fn file_open_roundtrip() -> TestResult {
+        let path = temp_path("file_open_roundtrip.bin");
+        let person = make_person();
+        let mut storage = Storage::open(&path, BorshSerializer);
         assert!(!storage.has_data());
-        storage.save(&make_person())?;
+        storage.save(&person)?;
         assert!(storage.has_data());
+        let loaded: Person = storage.load()?;
+        assert_eq!(loaded, person);
+        std::fs::remove_file(&path)?;
         Ok(())
     }
 
     #[test]
-    fn json_roundtrip() -> TestResult {
+    fn file_persists_across_instances() -> TestResult {
+        let path = temp_path("file_persists_across_instances.json");
         let person = make_person();
-        let mut storage = Storage::new(JsonSerializer);
-        storage.save(&person)?;
-        let loaded = storage.load()?;
-        assert_eq!(person, loaded);
+        {
+            let mut storage = Storage::open(&path, JsonSerializer);
+            storage.save(&person)?;
+        }
+        let reopened: Storage<Person, _, _> = Storage::open(&path, JsonSerializer);
+        assert_eq!(reopened.load()?, person);
+        std::fs::remove_file(&path)?;
         Ok(())
     }
 
     #[test]
-    fn json_name_preserved() -> TestResult {
-        let person = make_person();
-        let mut storage = Storage::new(JsonSerializer);
-        storage.save(&person)?;
-        let loaded: Person = storage.load()?;
-        assert_eq!(loaded.name, "Alice");
+    fn file_save_leaves_no_temp_file() -> TestResult {
+        let path = temp_path("file_save_leaves_no_temp_file.bin");
+        let mut storage = Storage::open(&path, WincodeSerializer);
+        storage.save(&make_person())?;
+        let mut tmp_name = path.as_os_str().to_owned();
+        tmp_name.push(".tmp");
+        assert!(!std::path::Path::new(&tmp_name).exists());
+        std::fs::remove_file(&path)?;
         Ok(())
     }
 
     #[test]
-    fn json_age_preserved() -> TestResult {
+    fn file_load_missing_returns_error() {
+        let path = temp_path("file_load_missing_returns_error.bin");
+        let storage: Storage<Person, _, _> = Storage::open(&path, BorshSerializer);
+        assert!(storage.load().is_err());
+    }
+
+    #[test]
+    fn file_convert_rewrites_file() -> TestResult {
+        let path = temp_path("file_convert_rewrites_file");
         let person = make_person();
-        let mut storage = Storage::new(JsonSerializer);
+        let mut storage = Storage::open(&path, BorshSerializer);
         storage.save(&person)?;
-        let loaded: Person = storage.load()?;
-        assert_eq!(loaded.age, 30);
+        let converted = storage.convert(JsonSerializer)?;
+        assert_eq!(std::fs::read(&path)?, serde_json::to_vec(&person)?);
+        assert_eq!(converted.load()?, person);
+        std::fs::remove_file(&path)?;
         Ok(())
     }
 
     #[test]
-    fn json_empty_name() -> TestResult {
-        let person = Person { name: "".to_string(), age: 0 };
-        let mut storage = Storage::new(JsonSerializer);
-        storage.save(&person)?;
+    fn directory_load_returns_latest() -> TestResult {
+        let dir = temp_path("directory_load_returns_latest");
+        let mut storage = Storage::with_backend(BorshSerializer, DirectoryBackend::new(&dir));
+        storage.save(&make_person())?;
+        let latest = Person { name: "Bob".to_string(), age: 41 };
+        storage.save(&latest)?;
+        assert_eq!(storage.backend().snapshots()?.len(), 2);
         let loaded: Person = storage.load()?;
-        assert_eq!(loaded, person);
+        assert_eq!(loaded, latest);
+        std::fs::remove_dir_all(&dir)?;
         Ok(())
     }
 
     #[test]
-    fn json_unicode_name() -> TestResult {
-        let person = Person { name: "Alice 🦀".to_string(), age: 99 };
-        let mut storage = Storage::new(JsonSerializer);
-        storage.save(&person)?;
+    fn directory_retain_prunes_old_snapshots() -> TestResult {
+        let dir = temp_path("directory_retain_prunes_old_snapshots");
+        let mut storage =
+            Storage::with_backend(JsonSerializer, DirectoryBackend::new(&dir).retain(2));
+        for age in 0..5 {
+            storage.save(&Person { name: "Alice".to_string(), age })?;
+        }
+        assert_eq!(storage.backend().snapshots()?.len(), 2);
         let loaded: Person = storage.load()?;
-        assert_eq!(loaded, person);
+        assert_eq!(loaded.age, 4);
+        std::fs::remove_dir_all(&dir)?;
         Ok(())
     }
 
     #[test]
-    fn json_max_age() -> TestResult {
-        let person = Person { name: "Bob".to_string(), age: u32::MAX };
-        let mut storage = Storage::new(JsonSerializer);
-        storage.save(&person)?;
-        let loaded: Person = storage.load()?;
-        assert_eq!(loaded.age, u32::MAX);
+    fn file_backend_reads_none_when_missing() -> TestResult {
+        let backend = FileBackend::new(temp_path("file_backend_reads_none_when_missing"));
+        assert!(backend.read()?.is_none());
+        assert!(!backend.exists());
         Ok(())
     }
 
     #[test]
-    fn json_has_data_after_save() -> TestResult {
-        let mut storage = Storage::new(JsonSerializer);
-        assert!(!storage.has_data());
-        storage.save(&make_person())?;
-        assert!(storage.has_data());
+    fn keyed_insert_and_get() -> TestResult {
+        let mut store = KeyedStorage::new(BorshSerializer, BorshSerializer);
+        let alice = make_person();
+        let bob = Person { name: "Bob".to_string(), age: 41 };
+        assert!(store.insert(&"alice".to_string(), &alice)?.is_none());
+        assert!(store.insert(&"bob".to_string(), &bob)?.is_none());
+        assert_eq!(store.len(), 2);
+        assert_eq!(store.get(&"alice".to_string())?, Some(alice));
+        assert_eq!(store.get(&"bob".to_string())?, Some(bob));
+        assert_eq!(store.get(&"carol".to_string())?, None);
         Ok(())
     }
 
     #[test]
-    fn wincode_roundtrip() -> TestResult {
-        let person = make_person();
-        let mut storage = Storage::new(WincodeSerializer);
-        storage.save(&person)?;
-        let loaded = storage.load()?;
-        assert_eq!(person, loaded);
+    fn keyed_insert_returns_previous() -> TestResult {
+        let mut store = KeyedStorage::new(JsonSerializer, JsonSerializer);
+        let key = 7u32;
+        store.insert(&key, &make_person())?;
+        let updated = Person { name: "Alice".to_string(), age: 31 };
+        assert_eq!(store.insert(&key, &updated)?, Some(make_person()));
+        assert_eq!(store.len(), 1);
+        assert_eq!(store.get(&key)?, Some(updated));
         Ok(())
     }
 
     #[test]
-    fn wincode_name_preserved() -> TestResult {
-        let person = make_person();
-        let mut storage = Storage::new(WincodeSerializer);
-        storage.save(&person)?;
-        let loaded: Person = storage.load()?;
-        assert_eq!(loaded.name, "Alice");
+    fn keyed_remove() -> TestResult {
+        let mut store = KeyedStorage::new(WincodeSerializer, WincodeSerializer);
+        let key = 1u64;
+        store.insert(&key, &make_person())?;
+        assert!(store.contains_key(&key)?);
+        assert_eq!(store.remove(&key)?, Some(make_person()));
+        assert!(!store.contains_key(&key)?);
+        assert_eq!(store.remove(&key)?, None);
+        assert!(store.is_empty());
         Ok(())
     }
 
     #[test]
-    fn wincode_age_preserved() -> TestResult {
-        let person = make_person();
-        let mut storage = Storage::new(WincodeSerializer);
-        storage.save(&person)?;
-        let loaded: Person = storage.load()?;
-        assert_eq!(loaded.age, 30);
+    fn keyed_iter_yields_all_entries() -> TestResult {
+        let mut store = KeyedStorage::new(BorshSerializer, BorshSerializer);
+        for age in 0..3u32 {
+            store.insert(&age, &Person { name: format!("p{age}"), age })?;
+        }
+        let entries = store.iter().collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(entries.len(), 3);
+        for (key, person) in entries {
+            assert_eq!(person.age, key);
+            assert_eq!(person.name, format!("p{key}"));
+        }
         Ok(())
     }
 
     #[test]
-    fn wincode_empty_name() -> TestResult {
-        let person = Person { name: "".to_string(), age: 0 };
-        let mut storage = Storage::new(WincodeSerializer);
-        storage.save(&person)?;
-        let loaded: Person = storage.load()?;
-        assert_eq!(loaded, person);
+    fn keyed_mixed_key_and_value_serializers() -> TestResult {
+        let mut store = KeyedStorage::new(JsonSerializer, BorshSerializer);
+        store.insert(&"alice".to_string(), &make_person())?;
+        let keys = store.keys().collect::<Result<Vec<String>, _>>()?;
+        assert_eq!(keys, vec!["alice".to_string()]);
         Ok(())
     }
 
     #[test]
-    fn wincode_unicode_name() -> TestResult {
-        let person = Person { name: "Alice 🦀".to_string(), age: 99 };
-        let mut storage = Storage::new(WincodeSerializer);
-        storage.save(&person)?;
-        let loaded: Person = storage.load()?;
-        assert_eq!(loaded, person);
+    fn keyed_convert_values() -> TestResult {
+        let mut store = KeyedStorage::new(BorshSerializer, BorshSerializer);
+        store.insert(&"alice".to_string(), &make_person())?;
+        let converted = store.convert(WincodeSerializer)?;
+        assert_eq!(converted.get(&"alice".to_string())?, Some(make_person()));
+        Ok(())
+    }
+
+    fn history_roundtrip<S: Serializer<Person>>(serializer: S) -> TestResult {
+        let mut storage = Storage::new(serializer).with_history(3);
+        for age in 1..=4 {
+            storage.save(&Person { name: "Alice".to_string(), age })?;
+        }
+        assert_eq!(storage.history_len(), 3);
+        assert_eq!(storage.load_version(0)?.age, 4);
+        assert_eq!(storage.load_version(1)?.age, 3);
+        assert_eq!(storage.load_version(2)?.age, 2);
+        assert!(storage.load_version(3).is_err());
+
+        storage.rollback()?;
+        assert_eq!(storage.load()?.age, 3);
+        assert_eq!(storage.history_len(), 2);
+        storage.rollback()?;
+        assert_eq!(storage.load()?.age, 2);
+        assert!(storage.rollback().is_err());
+        assert_eq!(storage.load()?.age, 2);
         Ok(())
     }
 
     #[test]
-    fn wincode_max_age() -> TestResult {
-        let person = Person { name: "Bob".to_string(), age: u32::MAX };
-        let mut storage = Storage::new(WincodeSerializer);
-        storage.save(&person)?;
-        let loaded: Person = storage.load()?;
-        assert_eq!(loaded.age, u32::MAX);
+    fn borsh_history_and_rollback() -> TestResult {
+        history_roundtrip(BorshSerializer)
+    }
+
+    #[test]
+    fn json_history_and_rollback() -> TestResult {
+        history_roundtrip(JsonSerializer)
+    }
+
+    #[test]
+    fn wincode_history_and_rollback() -> TestResult {
+        history_roundtrip(WincodeSerializer)
+    }
+
+    #[test]
+    fn history_disabled_by_default() -> TestResult {
+        let mut storage = Storage::new(BorshSerializer);
+        storage.save(&make_person())?;
+        storage.save(&make_person())?;
+        assert_eq!(storage.history_len(), 0);
+        assert!(storage.rollback().is_err());
         Ok(())
     }
 
     #[test]
-    fn wincode_has_data_after_save() -> TestResult {
-        let mut storage = Storage::new(WincodeSerializer);
-        assert!(!storage.has_data());
+    fn history_survives_convert() -> TestResult {
+        let mut storage = Storage::new(BorshSerializer).with_history(2);
         storage.save(&make_person())?;
-        assert!(storage.has_data());
+        storage.save(&Person { name: "Bob".to_string(), age: 41 })?;
+        let mut converted = storage.convert(JsonSerializer)?.convert(WincodeSerializer)?;
+        assert_eq!(converted.history_len(), 2);
+        assert_eq!(converted.load_version(1)?, make_person());
+        converted.rollback()?;
+        assert_eq!(converted.load()?, make_person());
         Ok(())
     }
 
     #[test]
-    fn borsh_load_without_save_returns_error() {
+    fn load_without_save_is_empty_error() {
         let storage: Storage<Person, _> = Storage::new(BorshSerializer);
-        assert!(storage.load().is_err());
+        assert!(matches!(storage.load(), Err(StorageError::Empty)));
     }
 
     #[test]
-    fn json_load_without_save_returns_error() {
-        let storage: Storage<Person, _> = Storage::new(JsonSerializer);
-        assert!(storage.load().is_err());
+    fn borsh_corrupt_bytes_is_decode_error() -> TestResult {
+        let path = temp_path("borsh_corrupt_bytes_is_decode_error");
+        std::fs::write(&path, [0xff, 0xff, 0xff, 0xff])?;
+        let storage: Storage<Person, _, _> = Storage::open(&path, BorshSerializer);
+        assert!(matches!(
+            storage.load(),
+            Err(StorageError::Decode { format: "borsh", .. })
+        ));
+        std::fs::remove_file(&path)?;
+        Ok(())
     }
 
     #[test]
-    fn wincode_load_without_save_returns_error() {
-        let storage: Storage<Person, _> = Storage::new(WincodeSerializer);
-        assert!(storage.load().is_err());
+    fn json_corrupt_bytes_reports_offset() -> TestResult {
+        let path = temp_path("json_corrupt_bytes_reports_offset");
+        std::fs::write(&path, br#"{"name":"Alice","age":x}"#)?;
+        let storage: Storage<Person, _, _> = Storage::open(&path, JsonSerializer);
+        match storage.load() {
+            Err(StorageError::Decode { format: "json", offset: Some(offset), .. }) => {
+                assert_eq!(offset, 22)
+            }
+            other => panic!("expected json decode error, got {other:?}"),
+        }
+        std::fs::remove_file(&path)?;
+        Ok(())
     }
 
     #[test]
-    fn convert_borsh_to_json() -> TestResult {
-        let person = make_person();
-        let mut storage = Storage::new(BorshSerializer);
-        storage.save(&person)?;
-        let converted = storage.convert(JsonSerializer)?;
-        let loaded: Person = converted.load()?;
-        assert_eq!(loaded, person);
+    fn wincode_truncated_bytes_is_decode_error() -> TestResult {
+        let mut storage = Storage::new(WincodeSerializer);
+        storage.save(&make_person())?;
+        let bytes = storage.into_backend().read()?.unwrap_or_default();
+        let path = temp_path("wincode_truncated_bytes_is_decode_error");
+        std::fs::write(&path, &bytes[..bytes.len() - 1])?;
+        let storage: Storage<Person, _, _> = Storage::open(&path, WincodeSerializer);
+        assert!(matches!(
+            storage.load(),
+            Err(StorageError::Decode { format: "wincode", .. })
+        ));
+        std::fs::remove_file(&path)?;
         Ok(())
     }
 
     #[test]
-    fn convert_borsh_to_wincode() -> TestResult {
-        let person = make_person();
-        let mut storage = Storage::new(BorshSerializer);
-        storage.save(&person)?;
-        let converted = storage.convert(WincodeSerializer)?;
-        let loaded: Person = converted.load()?;
-        assert_eq!(loaded, person);
+    fn rollback_without_history_is_version_not_found() {
+        let mut storage: Storage<Person, _> = Storage::new(JsonSerializer).with_history(2);
+        assert!(matches!(
+            storage.rollback(),
+            Err(StorageError::VersionNotFound { version: 1 })
+        ));
+    }
+
+    #[test]
+    fn envelope_roundtrip() -> TestResult {
+        let mut storage = Storage::new(BorshSerializer).with_envelope(1);
+        storage.save(&make_person())?;
+        let bytes = storage.backend().read()?.unwrap_or_default();
+        let (header, _) = envelope::unwrap(&bytes)?;
+        assert_eq!(header.format_id, 1);
+        assert_eq!(header.schema_version, 1);
+        assert_eq!(storage.load()?, make_person());
         Ok(())
     }
 
     #[test]
-    fn convert_json_to_borsh() -> TestResult {
-        let person = make_person();
-        let mut storage = Storage::new(JsonSerializer);
-        storage.save(&person)?;
-        let converted = storage.convert(BorshSerializer)?;
-        let loaded: Person = converted.load()?;
-        assert_eq!(loaded, person);
+    fn envelope_load_auto_dispatches_on_format() -> TestResult {
+        let path = temp_path("envelope_load_auto_dispatches_on_format");
+        {
+            let mut storage = Storage::open(&path, JsonSerializer).with_envelope(1);
+            storage.save(&make_person())?;
+        }
+        let storage: Storage<Person, _, _> = Storage::open(&path, WincodeSerializer);
+        assert_eq!(storage.load_auto()?, make_person());
+
+        let mut storage = storage.with_envelope(1);
+        storage.save(&make_person())?;
+        let reader: Storage<Person, _, _> = Storage::open(&path, BorshSerializer);
+        assert_eq!(reader.load_auto()?, make_person());
+        std::fs::remove_file(&path)?;
         Ok(())
     }
 
     #[test]
-    fn convert_json_to_wincode() -> TestResult {
-        let person = make_person();
-        let mut storage = Storage::new(JsonSerializer);
-        storage.save(&person)?;
-        let converted = storage.convert(WincodeSerializer)?;
-        let loaded: Person = converted.load()?;
-        assert_eq!(loaded, person);
+    fn envelope_rejects_wrong_serializer() -> TestResult {
+        let path = temp_path("envelope_rejects_wrong_serializer");
+        let mut storage = Storage::open(&path, BorshSerializer).with_envelope(1);
+        storage.save(&make_person())?;
+        let reader: Storage<Person, _, _> = Storage::open(&path, JsonSerializer).with_envelope(1);
+        assert!(matches!(
+            reader.load(),
+            Err(StorageError::FormatMismatch { expected: 2, found: 1 })
+        ));
+        std::fs::remove_file(&path)?;
         Ok(())
     }
 
     #[test]
-    fn convert_wincode_to_borsh() -> TestResult {
-        let person = make_person();
-        let mut storage = Storage::new(WincodeSerializer);
-        storage.save(&person)?;
-        let converted = storage.convert(BorshSerializer)?;
-        let loaded: Person = converted.load()?;
-        assert_eq!(loaded, person);
+    fn envelope_detects_corruption() -> TestResult {
+        let path = temp_path("envelope_detects_corruption");
+        let mut storage = Storage::open(&path, WincodeSerializer).with_envelope(1);
+        storage.save(&make_person())?;
+        let mut bytes = std::fs::read(&path)?;
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        std::fs::write(&path, &bytes)?;
+        assert!(matches!(storage.load(), Err(StorageError::Integrity(_))));
+        assert!(matches!(storage.load_auto(), Err(StorageError::Integrity(_))));
+        std::fs::remove_file(&path)?;
         Ok(())
     }
 
     #[test]
-    fn convert_wincode_to_json() -> TestResult {
-        let person = make_person();
-        let mut storage = Storage::new(WincodeSerializer);
-        storage.save(&person)?;
+    fn envelope_survives_convert() -> TestResult {
+        let mut storage = Storage::new(BorshSerializer).with_envelope(3).with_history(2);
+        storage.save(&make_person())?;
+        storage.save(&Person { name: "Bob".to_string(), age: 41 })?;
         let converted = storage.convert(JsonSerializer)?;
-        let loaded: Person = converted.load()?;
-        assert_eq!(loaded, person);
+        let bytes = converted.backend().read()?.unwrap_or_default();
+        let (header, _) = envelope::unwrap(&bytes)?;
+        assert_eq!(header.format_id, 2);
+        assert_eq!(header.schema_version, 3);
+        assert_eq!(converted.load_auto()?.name, "Bob");
+        assert_eq!(converted.load_version(1)?, make_person());
         Ok(())
     }
 
     #[test]
-    fn convert_preserves_has_data() -> TestResult {
-        let person = make_person();
+    fn load_auto_requires_envelope() -> TestResult {
         let mut storage = Storage::new(BorshSerializer);
-        storage.save(&person)?;
-        let converted = storage.convert(JsonSerializer)?;
-        assert!(converted.has_data());
+        storage.save(&make_person())?;
+        assert!(matches!(storage.load_auto(), Err(StorageError::Integrity(_))));
         Ok(())
     }
 
-    #[test]
-    fn convert_without_save_returns_error() {
-        let storage: Storage<Person, _> = Storage::new(BorshSerializer);
-        assert!(storage.convert(JsonSerializer).is_err());
+    #[derive(
+        Debug,
+        PartialEq,
+        ::borsh::BorshSerialize,
+        ::borsh::BorshDeserialize,
+        ::serde::Serialize,
+        ::serde::Deserialize,
+        ::wincode::SchemaWrite,
+        ::wincode::SchemaRead,
+    )]
+    struct PersonV2 {
+        name: String,
+        age: u32,
+        email: Option<String>,
     }
 
-    #[test]
-    fn convert_chain_borsh_json_wincode() -> TestResult {
-        let person = make_person();
-        let mut storage = Storage::new(BorshSerializer);
-        storage.save(&person)?;
-        let loaded: Person = storage
-            .convert(JsonSerializer)?
-            .convert(WincodeSerializer)?
-            .load()?;
-        assert_eq!(loaded, person);
+    impl<S> Migrate<S> for PersonV2
+    where
+        S: Serializer<Person> + Serializer<PersonV2>,
+    {
+        const SCHEMA_VERSION: u16 = 2;
+
+        fn migrate_from(serializer: &S, version: u16, bytes: &[u8]) -> Result<Vec<u8>, StorageError> {
+            match version {
+                1 => {
+                    let old: Person = serializer.from_bytes(bytes)?;
+                    serializer.to_bytes(&PersonV2 { name: old.name, age: old.age, email: None })
+                }
+                from => Err(StorageError::NoMigration { from }),
+            }
+        }
+    }
+
+    fn migrate_person_v1<S>(old_serializer: S, new_serializer: S) -> TestResult
+    where
+        S: Serializer<Person> + Serializer<PersonV2>,
+    {
+        let mut old = Storage::new(old_serializer).with_envelope(1);
+        old.save(&make_person())?;
+
+        let mut storage: Storage<PersonV2, _> =
+            Storage::with_backend(new_serializer, old.into_backend()).with_migrations();
+        let migrated = storage.load()?;
+        assert_eq!(migrated, PersonV2 { name: "Alice".to_string(), age: 30, email: None });
+
+        let updated = PersonV2 { email: Some("alice@example.com".to_string()), ..migrated };
+        storage.save(&updated)?;
+        let bytes = storage.backend().read()?.unwrap_or_default();
+        assert_eq!(envelope::unwrap(&bytes)?.0.schema_version, 2);
+        assert_eq!(storage.load()?, updated);
         Ok(())
     }
 
     #[test]
-    fn all_serializers_same_result() -> TestResult {
-        let person = make_person();
+    fn borsh_migrates_added_field() -> TestResult {
+        migrate_person_v1(BorshSerializer, BorshSerializer)
+    }
 
-        let mut s1 = Storage::new(BorshSerializer);
-        s1.save(&person)?;
-        let r1: Person = s1.load()?;
+    #[test]
+    fn json_migrates_added_field() -> TestResult {
+        migrate_person_v1(JsonSerializer, JsonSerializer)
+    }
 
-        let mut s2 = Storage::new(JsonSerializer);
-        s2.save(&person)?;
-        let r2: Person = s2.load()?;
+    #[test]
+    fn wincode_migrates_added_field() -> TestResult {
+        migrate_person_v1(WincodeSerializer, WincodeSerializer)
+    }
 
-        let mut s3 = Storage::new(WincodeSerializer);
-        s3.save(&person)?;
-        let r3: Person = s3.load()?;
+    #[test]
+    fn migration_rejects_newer_schema() -> TestResult {
+        let mut newer = Storage::new(BorshSerializer).with_envelope(3);
+        newer.save(&PersonV2 { name: "Alice".to_string(), age: 30, email: None })?;
+        let storage: Storage<PersonV2, _> =
+            Storage::with_backend(BorshSerializer, newer.into_backend()).with_migrations();
+        assert!(matches!(
+            storage.load(),
+            Err(StorageError::SchemaVersion { found: 3, current: 2 })
+        ));
+        Ok(())
+    }
 
-        assert_eq!(r1, r2);
-        assert_eq!(r2, r3);
+    #[test]
+    fn envelope_rejects_other_schema_without_migrations() -> TestResult {
+        let mut old = Storage::new(BorshSerializer).with_envelope(1);
+        old.save(&make_person())?;
+        let storage: Storage<Person, _> = Storage::with_backend(BorshSerializer, old.into_backend()).with_envelope(2);
+        assert!(matches!(
+            storage.load(),
+            Err(StorageError::SchemaVersion { found: 1, current: 2 })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn migration_missing_step_is_error() -> TestResult {
+        let mut old = Storage::new(JsonSerializer).with_envelope(0);
+        old.save(&make_person())?;
+        let storage: Storage<PersonV2, _> =
+            Storage::with_backend(JsonSerializer, old.into_backend()).with_migrations();
+        assert!(matches!(storage.load(), Err(StorageError::NoMigration { from: 0 })));
+        Ok(())
+    }
+
+    #[cfg(any(feature = "bincode", feature = "msgpack", feature = "cbor", feature = "postcard"))]
+    fn roundtrip_matrix<S: Serializer<Person>>(serializer: S) -> TestResult {
+        let mut storage = Storage::new(serializer);
+        let people = [
+            make_person(),
+            Person { name: "".to_string(), age: 0 },
+            Person { name: "Alice 🦀".to_string(), age: 99 },
+            Person { name: "Bob".to_string(), age: u32::MAX },
+        ];
+        for person in people {
+            storage.save(&person)?;
+            assert_eq!(storage.load()?, person);
+        }
+        Ok(())
+    }
+
+    #[cfg(any(feature = "bincode", feature = "msgpack", feature = "cbor", feature = "postcard"))]
+    fn convert_through<S: Serializer<Person>>(serializer: S) -> TestResult {
+        let mut storage = Storage::new(BorshSerializer);
+        storage.save(&make_person())?;
+        let loaded: Person = storage
+            .convert(serializer)?
+            .convert(WincodeSerializer)?
+            .convert(JsonSerializer)?
+            .load()?;
+        assert_eq!(loaded, make_person());
+        Ok(())
+    }
+
+    #[cfg(any(feature = "bincode", feature = "msgpack", feature = "cbor", feature = "postcard"))]
+    fn load_auto_from<S: Serializer<Person>>(serializer: S) -> TestResult {
+        let mut storage = Storage::new(serializer).with_envelope(1);
+        storage.save(&make_person())?;
+        let reader: Storage<Person, _, _> =
+            Storage::with_backend(BorshSerializer, storage.into_backend());
+        assert_eq!(reader.load_auto()?, make_person());
+        Ok(())
+    }
+
+    #[cfg(feature = "bincode")]
+    #[test]
+    fn bincode_roundtrip_matrix() -> TestResult {
+        roundtrip_matrix(BincodeSerializer)
+    }
+
+    #[cfg(feature = "bincode")]
+    #[test]
+    fn bincode_convert_matrix() -> TestResult {
+        convert_through(BincodeSerializer)
+    }
+
+    #[cfg(feature = "bincode")]
+    #[test]
+    fn bincode_load_auto() -> TestResult {
+        load_auto_from(BincodeSerializer)
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[test]
+    fn msgpack_roundtrip_matrix() -> TestResult {
+        roundtrip_matrix(MsgPackSerializer)
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[test]
+    fn msgpack_convert_matrix() -> TestResult {
+        convert_through(MsgPackSerializer)
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[test]
+    fn msgpack_load_auto() -> TestResult {
+        load_auto_from(MsgPackSerializer)
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn cbor_roundtrip_matrix() -> TestResult {
+        roundtrip_matrix(CborSerializer)
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn cbor_convert_matrix() -> TestResult {
+        convert_through(CborSerializer)
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn cbor_load_auto() -> TestResult {
+        load_auto_from(CborSerializer)
+    }
+
+    #[cfg(feature = "postcard")]
+    #[test]
+    fn postcard_roundtrip_matrix() -> TestResult {
+        roundtrip_matrix(PostcardSerializer)
+    }
+
+    #[cfg(feature = "postcard")]
+    #[test]
+    fn postcard_convert_matrix() -> TestResult {
+        convert_through(PostcardSerializer)
+    }
+
+    #[cfg(feature = "postcard")]
+    #[test]
+    fn postcard_load_auto() -> TestResult {
+        load_auto_from(PostcardSerializer)
+    }
+
+    #[test]
+    fn json_with_view_borrows_fields() -> TestResult {
+        let mut storage = Storage::new(JsonSerializer);
+        storage.save(&make_person())?;
+        let view = storage.with_view(|view| (view.name.to_string(), view.age))?;
+        assert_eq!(view, ("Alice".to_string(), 30));
+        Ok(())
+    }
+
+    #[test]
+    fn wincode_with_view_borrows_fields() -> TestResult {
+        let mut storage = Storage::new(WincodeSerializer);
+        storage.save(&make_person())?;
+        let matches = storage.with_view(|view| view == PersonView { name: "Alice", age: 30 })?;
+        assert!(matches);
+        Ok(())
+    }
+
+    #[test]
+    fn with_view_through_file_and_envelope() -> TestResult {
+        let path = temp_path("with_view_through_file_and_envelope");
+        let mut storage = Storage::open(&path, JsonSerializer).with_envelope(1);
+        storage.save(&Person { name: "Alice 🦀".to_string(), age: 99 })?;
+        let age = storage.with_view(|view| {
+            assert_eq!(view.name, "Alice 🦀");
+            view.age
+        })?;
+        assert_eq!(age, 99);
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn with_view_without_save_is_empty_error() {
+        let storage: Storage<Person, _> = Storage::new(WincodeSerializer);
+        assert!(matches!(storage.with_view(|view| view.age), Err(StorageError::Empty)));
+    }
+
+    fn stream_roundtrip<S: Serializer<Person>>(serializer: S) -> TestResult {
+        let person = Person { name: "Alice 🦀".to_string(), age: u32::MAX };
+        let mut buffer = Vec::new();
+        serializer.serialize_into(&person, &mut buffer)?;
+        assert_eq!(buffer, serializer.to_bytes(&person)?);
+        let loaded = serializer.deserialize_from(buffer.as_slice())?;
+        assert_eq!(loaded, person);
+        Ok(())
+    }
+
+    #[test]
+    fn borsh_stream_roundtrip() -> TestResult {
+        stream_roundtrip(BorshSerializer)
+    }
+
+    #[test]
+    fn json_stream_roundtrip() -> TestResult {
+        stream_roundtrip(JsonSerializer)
+    }
+
+    #[test]
+    fn wincode_stream_roundtrip() -> TestResult {
+        stream_roundtrip(WincodeSerializer)
+    }
+
+    #[test]
+    fn borsh_stream_consecutive_records() -> TestResult {
+        let path = temp_path("borsh_stream_consecutive_records");
+        let people = [make_person(), Person { name: "Bob".to_string(), age: 41 }];
+        {
+            let mut file = std::io::BufWriter::new(std::fs::File::create(&path)?);
+            for person in &people {
+                BorshSerializer.serialize_into(person, &mut file)?;
+            }
+        }
+        let mut file = std::io::BufReader::new(std::fs::File::open(&path)?);
+        for person in &people {
+            let loaded: Person = BorshSerializer.deserialize_from(&mut file)?;
+            assert_eq!(&loaded, person);
+        }
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn json_stream_reports_io_errors() {
+        struct FailingWriter;
+        impl std::io::Write for FailingWriter {
+            fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::other("socket closed"))
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+        let result = JsonSerializer.serialize_into(&make_person(), FailingWriter);
+        assert!(matches!(result, Err(StorageError::Io(_))));
+    }
+
+    #[cfg(any(feature = "zstd", feature = "lz4", feature = "deflate"))]
+    fn compressed_roundtrip<C: Compression>(codec: impl Fn() -> C) -> TestResult {
+        let person = Person { name: "Alice".repeat(200), age: 30 };
+
+        let mut borsh = Storage::new(Compressed::new(BorshSerializer, codec()));
+        borsh.save(&person)?;
+        assert_eq!(borsh.load()?, person);
+        let compressed_len = borsh.backend().read()?.unwrap_or_default().len();
+        assert!(compressed_len < BorshSerializer.to_bytes(&person)?.len());
+
+        let mut json = Storage::new(Compressed::new(JsonSerializer, codec()));
+        json.save(&person)?;
+        assert_eq!(json.load()?, person);
+
+        let mut wincode = Storage::new(Compressed::new(WincodeSerializer, codec()));
+        wincode.save(&person)?;
+        assert_eq!(wincode.load()?, person);
+
+        let converted = json
+            .convert(Compressed::new(BorshSerializer, codec()))?
+            .convert(WincodeSerializer)?;
+        assert_eq!(converted.load()?, person);
+        Ok(())
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn zstd_compressed_roundtrip() -> TestResult {
+        compressed_roundtrip(Zstd::default)
+    }
+
+    #[cfg(feature = "lz4")]
+    #[test]
+    fn lz4_compressed_roundtrip() -> TestResult {
+        compressed_roundtrip(|| Lz4)
+    }
+
+    #[cfg(feature = "deflate")]
+    #[test]
+    fn deflate_compressed_roundtrip() -> TestResult {
+        compressed_roundtrip(Deflate::default)
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn compressed_corrupt_bytes_is_decode_error() -> TestResult {
+        let path = temp_path("compressed_corrupt_bytes_is_decode_error");
+        std::fs::write(&path, b"not zstd")?;
+        let storage: Storage<Person, _, _> =
+            Storage::open(&path, Compressed::new(BorshSerializer, Zstd::default()));
+        assert!(matches!(storage.load(), Err(StorageError::Decode { format: "zstd", .. })));
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[cfg(feature = "encryption")]
+    #[test]
+    fn encrypted_roundtrip_all_serializers() -> TestResult {
+        let key = Encrypted::<BorshSerializer>::generate_key();
+        let person = make_person();
+
+        let mut borsh = Storage::new(Encrypted::new(BorshSerializer, &key));
+        borsh.save(&person)?;
+        assert_eq!(borsh.load()?, person);
+
+        let mut json = Storage::new(Encrypted::new(JsonSerializer, &key));
+        json.save(&person)?;
+        assert_eq!(json.load()?, person);
+        let bytes = json.backend().read()?.unwrap_or_default();
+        assert!(!bytes.windows(5).any(|window| window == b"Alice"));
+
+        let mut wincode = Storage::new(Encrypted::new(WincodeSerializer, &key));
+        wincode.save(&person)?;
+        assert_eq!(wincode.load()?, person);
+        Ok(())
+    }
+
+    #[cfg(feature = "encryption")]
+    #[test]
+    fn encrypted_detects_tampering() -> TestResult {
+        let path = temp_path("encrypted_detects_tampering");
+        let key = Encrypted::<JsonSerializer>::generate_key();
+        let mut storage = Storage::open(&path, Encrypted::new(JsonSerializer, &key));
+        storage.save(&make_person())?;
+
+        let mut bytes = std::fs::read(&path)?;
+        bytes[15] ^= 0x01;
+        std::fs::write(&path, &bytes)?;
+        assert!(matches!(storage.load(), Err(StorageError::Tampered)));
+
+        std::fs::write(&path, &bytes[..8])?;
+        assert!(matches!(storage.load(), Err(StorageError::Tampered)));
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[cfg(feature = "encryption")]
+    #[test]
+    fn encrypted_wrong_key_is_tampered() -> TestResult {
+        let mut storage = Storage::new(Encrypted::new(BorshSerializer, &[7; 32]));
+        storage.save(&make_person())?;
+        let reader: Storage<Person, _> =
+            Storage::with_backend(Encrypted::new(BorshSerializer, &[8; 32]), storage.into_backend());
+        assert!(matches!(reader.load(), Err(StorageError::Tampered)));
+        Ok(())
+    }
+
+    #[cfg(feature = "encryption")]
+    #[test]
+    fn encrypted_convert_to_plaintext() -> TestResult {
+        let key = Encrypted::<BorshSerializer>::generate_key();
+        let mut storage = Storage::new(Encrypted::new(BorshSerializer, &key)).with_envelope(1);
+        storage.save(&make_person())?;
+        let converted = storage.convert(JsonSerializer)?;
+        assert_eq!(converted.load_auto()?, make_person());
+        Ok(())
+    }
+
+    fn person_strategy() -> impl proptest::strategy::Strategy<Value = Person> {
+        use proptest::prelude::*;
+
+        ("\\PC*", prop_oneof![Just(0u32), Just(u32::MAX), any::<u32>()])
+            .prop_map(|(name, age)| Person { name, age })
+    }
+
+    crate::serializer_test_suite!(person_suite {
+        type: Person,
+        samples: [
+            make_person(),
+            Person { name: "".to_string(), age: 0 },
+            Person { name: "Alice 🦀".to_string(), age: 99 },
+            Person { name: "Bob".to_string(), age: u32::MAX },
+        ],
+        serializers: {
+            borsh: BorshSerializer,
+            json: JsonSerializer,
+            wincode: WincodeSerializer,
+        },
+        strategy: person_strategy(),
+    });
+
+    #[cfg(all(feature = "bincode", feature = "msgpack", feature = "cbor", feature = "postcard"))]
+    crate::serializer_test_suite!(person_all_formats_suite {
+        type: Person,
+        samples: [make_person(), Person { name: "Alice 🦀".to_string(), age: u32::MAX }],
+        serializers: {
+            borsh: BorshSerializer,
+            json: JsonSerializer,
+            wincode: WincodeSerializer,
+            bincode: BincodeSerializer,
+            msgpack: MsgPackSerializer,
+            cbor: CborSerializer,
+            postcard: PostcardSerializer,
+        },
+        strategy: person_strategy(),
+    });
+
+    #[test]
+    fn encoded_len_matches_to_bytes() -> TestResult {
+        let person = Person { name: "Alice 🦀".to_string(), age: u32::MAX };
+        assert_eq!(BorshSerializer.encoded_len(&person)?, BorshSerializer.to_bytes(&person)?.len());
+        assert_eq!(JsonSerializer.encoded_len(&person)?, JsonSerializer.to_bytes(&person)?.len());
+        assert_eq!(WincodeSerializer.encoded_len(&person)?, WincodeSerializer.to_bytes(&person)?.len());
+        Ok(())
+    }
+
+    #[test]
+    fn compare_formats_reports_builtin_serializers() -> TestResult {
+        let reports = compare_formats(&make_person())?;
+        let formats: Vec<_> = reports.iter().map(|report| report.format).collect();
+        assert_eq!(&formats[..3], &["borsh", "json", "wincode"]);
+        for report in &reports {
+            assert!(report.canonical, "{} should be canonical", report.format);
+        }
+        assert_eq!(reports[0].encoded_len, 4 + 5 + 4);
+        assert_eq!(reports[1].encoded_len, br#"{"name":"Alice","age":30}"#.len());
+        Ok(())
+    }
+
+    #[cfg(feature = "encryption")]
+    #[test]
+    fn report_format_flags_non_canonical_encoding() -> TestResult {
+        use crate::report::report_format;
+
+        let serializer = Encrypted::new(BorshSerializer, &[1; 32]);
+        let report = report_format("borsh+chacha20poly1305", &serializer, &make_person())?;
+        assert!(!report.canonical);
+        Ok(())
+    }
+
+    #[test]
+    fn shared_storage_clones_share_state() -> TestResult {
+        let storage = SharedStorage::new(BorshSerializer);
+        let clone = storage.clone();
+        storage.save(&make_person())?;
+        assert_eq!(clone.load()?, make_person());
+        assert!(clone.has_data());
+        Ok(())
+    }
+
+    #[test]
+    fn shared_storage_update_on_empty_is_empty_error() {
+        let storage: SharedStorage<Person, _> = SharedStorage::new(BorshSerializer);
+        let err = storage.update(|person| person).unwrap_err();
+        assert!(err.is_empty());
+    }
+
+    #[test]
+    fn shared_storage_concurrent_updates_are_not_lost() -> TestResult {
+        const THREADS: u32 = 8;
+        const UPDATES: u32 = 200;
+
+        let storage = SharedStorage::new(BorshSerializer);
+        storage.save(&Person { name: "counter".to_string(), age: 0 })?;
+
+        let handles: Vec<_> = (0..THREADS)
+            .map(|_| {
+                let storage = storage.clone();
+                std::thread::spawn(move || -> Result<(), StorageError> {
+                    for _ in 0..UPDATES {
+                        storage.update(|mut person| {
+                            person.age += 1;
+                            person
+                        })?;
+                        assert_eq!(storage.load()?.name, "counter");
+                    }
+                    Ok(())
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            handle.join().expect("worker panicked")?;
+        }
+        assert_eq!(storage.load()?.age, THREADS * UPDATES);
+        Ok(())
+    }
+
+    #[test]
+    fn shared_storage_concurrent_saves_and_loads_on_file() -> TestResult {
+        let path = temp_path("shared.bin");
+        let storage = SharedStorage::open(&path, JsonSerializer);
+        storage.save(&Person { name: "writer-0".to_string(), age: 0 })?;
+
+        let writers: Vec<_> = (0..4u32)
+            .map(|id| {
+                let storage = storage.clone();
+                std::thread::spawn(move || -> Result<(), StorageError> {
+                    for age in 0..50 {
+                        storage.save(&Person { name: format!("writer-{id}"), age })?;
+                    }
+                    Ok(())
+                })
+            })
+            .collect();
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let storage = storage.clone();
+                std::thread::spawn(move || -> Result<(), StorageError> {
+                    for _ in 0..50 {
+                        let person = storage.load()?;
+                        assert!(person.name.starts_with("writer-"));
+                        assert!(person.age < 50);
+                    }
+                    Ok(())
+                })
+            })
+            .collect();
+
+        for handle in writers.into_iter().chain(readers) {
+            handle.join().expect("worker panicked")?;
+        }
+        assert!(storage.load()?.name.starts_with("writer-"));
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn async_storage_roundtrip_all_serializers() -> TestResult {
+        let person = make_person();
+
+        let mut borsh = AsyncStorage::new(BorshSerializer);
+        borsh.save(&person).await?;
+        assert_eq!(borsh.load().await?, person);
+
+        let mut json = AsyncStorage::new(JsonSerializer);
+        json.save(&person).await?;
+        assert_eq!(json.load().await?, person);
+
+        let mut wincode = AsyncStorage::new(WincodeSerializer);
+        wincode.save(&person).await?;
+        assert_eq!(wincode.load().await?, person);
+        Ok(())
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn async_storage_load_without_save_returns_error() {
+        let storage: AsyncStorage<Person, _> = AsyncStorage::new(BorshSerializer);
+        assert!(!storage.has_data().await);
+        assert!(storage.load().await.unwrap_err().is_empty());
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn async_file_storage_is_readable_by_sync_storage() -> TestResult {
+        let path = temp_path("async.bin");
+        let person = make_person();
+
+        let mut storage = AsyncStorage::open(&path, BorshSerializer).with_envelope(1);
+        storage.save(&person).await?;
+        assert!(storage.has_data().await);
+        assert_eq!(storage.load().await?, person);
+
+        let sync: Storage<Person, _, _> = Storage::open(&path, BorshSerializer).with_envelope(1);
+        assert_eq!(sync.load()?, person);
+
+        let reopened: AsyncStorage<Person, _, _> = AsyncStorage::open(&path, JsonSerializer).with_envelope(1);
+        assert!(matches!(
+            reopened.load().await,
+            Err(StorageError::FormatMismatch { .. })
+        ));
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn async_storage_rejects_other_schema_without_migrations() -> TestResult {
+        let mut old = Storage::new(JsonSerializer).with_envelope(3);
+        old.save(&make_person())?;
+        let storage: AsyncStorage<Person, _> =
+            AsyncStorage::with_backend(JsonSerializer, old.into_backend()).with_envelope(2);
+        assert!(matches!(
+            storage.load().await,
+            Err(StorageError::SchemaVersion { found: 3, current: 2 })
+        ));
+        Ok(())
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn async_storage_migrates_sync_blob() -> TestResult {
+        let mut old = Storage::new(WincodeSerializer).with_envelope(1);
+        old.save(&make_person())?;
+
+        let mut storage: AsyncStorage<PersonV2, _> =
+            AsyncStorage::with_backend(WincodeSerializer, old.into_backend()).with_migrations();
+        let migrated = storage.load().await?;
+        assert_eq!(migrated, PersonV2 { name: "Alice".to_string(), age: 30, email: None });
+
+        storage.save(&migrated).await?;
+        let bytes = StorageBackend::read(storage.backend())?.unwrap_or_default();
+        assert_eq!(envelope::unwrap(&bytes)?.0.schema_version, 2);
+        Ok(())
+    }
+
+    // Same fields as the anchor-escrow `Escrow` account, with `Pubkey` as
+    // raw bytes.
+    #[cfg(feature = "anchor")]
+    #[derive(Debug, PartialEq, ::borsh::BorshSerialize, ::borsh::BorshDeserialize)]
+    struct AnchorEscrow {
+        seed: u64,
+        maker: [u8; 32],
+        mint_a: [u8; 32],
+        mint_b: [u8; 32],
+        receive: u64,
+        created_at: i64,
+        bump: u8,
+    }
+
+    #[cfg(feature = "anchor")]
+    fn make_anchor_escrow() -> AnchorEscrow {
+        AnchorEscrow {
+            seed: 42,
+            maker: [1; 32],
+            mint_a: [2; 32],
+            mint_b: [3; 32],
+            receive: 1_000_000,
+            created_at: 1_700_000_000,
+            bump: 254,
+        }
+    }
+
+    #[cfg(feature = "anchor")]
+    #[test]
+    fn anchor_account_roundtrip() -> TestResult {
+        let serializer = AnchorAccountSerializer::for_account("Escrow");
+        assert_eq!(serializer.discriminator(), [31, 213, 123, 187, 186, 22, 218, 155]);
+
+        let escrow = make_anchor_escrow();
+        let bytes = serializer.to_bytes(&escrow)?;
+        assert_eq!(bytes.len(), 8 + 8 + 32 * 3 + 8 + 8 + 1);
+        assert_eq!(bytes[..8], serializer.discriminator());
+
+        let mut storage = Storage::new(serializer);
+        storage.save(&escrow)?;
+        assert_eq!(storage.load()?, escrow);
+        Ok(())
+    }
+
+    #[cfg(feature = "anchor")]
+    #[test]
+    fn anchor_account_ignores_trailing_space() -> TestResult {
+        let serializer = AnchorAccountSerializer::for_account("Escrow");
+        let escrow = make_anchor_escrow();
+        let mut bytes = serializer.to_bytes(&escrow)?;
+        bytes.resize(bytes.len() + 64, 0);
+        let loaded: AnchorEscrow = serializer.from_bytes(&bytes)?;
+        assert_eq!(loaded, escrow);
+        Ok(())
+    }
+
+    #[cfg(feature = "anchor")]
+    #[test]
+    fn anchor_account_rejects_wrong_discriminator() -> TestResult {
+        let bytes = AnchorAccountSerializer::for_account("Escrow").to_bytes(&make_anchor_escrow())?;
+        let other = AnchorAccountSerializer::for_account("Vault");
+        let result: Result<AnchorEscrow, _> = other.from_bytes(&bytes);
+        assert!(matches!(result, Err(StorageError::Decode { format: "anchor", offset: Some(0), .. })));
+
+        let result: Result<AnchorEscrow, _> = other.from_bytes(&bytes[..4]);
+        assert!(matches!(result, Err(StorageError::Decode { format: "anchor", .. })));
+        Ok(())
+    }
+
+    // The original 121-byte pinocchio-escrow `Escrow` layout, from before the
+    // account gained a version byte, an expiry and an allowed taker.
+    #[cfg(feature = "pod")]
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, PartialEq, ::bytemuck::Pod, ::bytemuck::Zeroable)]
+    struct PodEscrow {
+        maker: [u8; 32],
+        mint_a: [u8; 32],
+        mint_b: [u8; 32],
+        amount_to_receive: [u8; 8],
+        amount_to_give: [u8; 8],
+        seed: [u8; 8],
+        bump: u8,
+    }
+
+    #[cfg(feature = "pod")]
+    fn make_pod_escrow() -> PodEscrow {
+        PodEscrow {
+            maker: [1; 32],
+            mint_a: [2; 32],
+            mint_b: [3; 32],
+            amount_to_receive: 500u64.to_le_bytes(),
+            amount_to_give: 1_000u64.to_le_bytes(),
+            seed: 7u64.to_le_bytes(),
+            bump: 255,
+        }
+    }
+
+    #[cfg(feature = "pod")]
+    #[test]
+    fn pod_roundtrip() -> TestResult {
+        let escrow = make_pod_escrow();
+        let bytes = PodSerializer.to_bytes(&escrow)?;
+        assert_eq!(bytes.len(), 121);
+        assert_eq!(bytes[96..104], 500u64.to_le_bytes());
+
+        let mut storage = Storage::new(PodSerializer);
+        storage.save(&escrow)?;
+        assert_eq!(storage.load()?, escrow);
+        Ok(())
+    }
+
+    #[cfg(feature = "pod")]
+    #[test]
+    fn pod_views_account_data_in_place() -> TestResult {
+        use crate::serializer::BorrowingSerializer;
+
+        let escrow = make_pod_escrow();
+        let bytes = PodSerializer.to_bytes(&escrow)?;
+        let view: &PodEscrow = PodSerializer.view_bytes(&bytes)?;
+        assert_eq!(*view, escrow);
+        assert_eq!(std::ptr::from_ref(view).cast::<u8>(), bytes.as_ptr());
+        Ok(())
+    }
+
+    #[cfg(feature = "pod")]
+    #[test]
+    fn pod_rejects_wrong_length() -> TestResult {
+        let bytes = PodSerializer.to_bytes(&make_pod_escrow())?;
+        let result: Result<PodEscrow, _> = PodSerializer.from_bytes(&bytes[..120]);
+        assert!(matches!(result, Err(StorageError::Decode { format: "pod", .. })));
+        Ok(())
+    }
+
+    fn truncate_by(path: &std::path::Path, bytes: u64) -> std::io::Result<()> {
+        let file = std::fs::OpenOptions::new().write(true).open(path)?;
+        let len = file.metadata()?.len();
+        file.set_len(len - bytes)
+    }
+
+    #[test]
+    fn wal_replays_last_save() -> TestResult {
+        let path = temp_path("wal_replays_last_save.log");
+        let mut storage = Storage::open_wal(&path, BorshSerializer)?;
+        storage.save(&make_person())?;
+        storage.save(&Person { name: "Bob".to_string(), age: 41 })?;
+        drop(storage);
+
+        let reopened: Storage<Person, _, _> = Storage::open_wal(&path, BorshSerializer)?;
+        assert_eq!(reopened.load()?, Person { name: "Bob".to_string(), age: 41 });
+        assert_eq!(reopened.backend().records(), 2);
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn wal_truncated_write_recovers_previous_value() -> TestResult {
+        let path = temp_path("wal_truncated_write.log");
+        let mut storage = Storage::open_wal(&path, JsonSerializer)?;
+        storage.save(&make_person())?;
+        storage.save(&Person { name: "Bob".to_string(), age: 41 })?;
+        drop(storage);
+
+        // Cut the second record short, as a crash mid-append would.
+        truncate_by(&path, 5)?;
+        let valid_len = std::fs::metadata(&path)?.len();
+
+        let mut reopened = Storage::open_wal(&path, JsonSerializer)?;
+        assert_eq!(reopened.load()?, make_person());
+        assert_eq!(reopened.backend().records(), 1);
+        assert!(std::fs::metadata(&path)?.len() < valid_len);
+
+        // The torn tail is gone, so new records are replayed normally.
+        reopened.save(&Person { name: "Carol".to_string(), age: 25 })?;
+        drop(reopened);
+        let reopened: Storage<Person, _, _> = Storage::open_wal(&path, JsonSerializer)?;
+        assert_eq!(reopened.load()?, Person { name: "Carol".to_string(), age: 25 });
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn wal_truncated_header_recovers_previous_value() -> TestResult {
+        let path = temp_path("wal_truncated_header.log");
+        let mut storage = Storage::open_wal(&path, BorshSerializer)?;
+        storage.save(&make_person())?;
+        drop(storage);
+
+        let mut file = std::fs::OpenOptions::new().append(true).open(&path)?;
+        std::io::Write::write_all(&mut file, &[7, 0, 0])?;
+        drop(file);
+
+        let reopened: Storage<Person, _, _> = Storage::open_wal(&path, BorshSerializer)?;
+        assert_eq!(reopened.load()?, make_person());
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn wal_corrupt_record_recovers_previous_value() -> TestResult {
+        let path = temp_path("wal_corrupt_record.log");
+        let mut storage = Storage::open_wal(&path, BorshSerializer)?;
+        storage.save(&make_person())?;
+        storage.save(&Person { name: "Bob".to_string(), age: 41 })?;
+        drop(storage);
+
+        let mut log = std::fs::read(&path)?;
+        let last = log.len() - 1;
+        log[last] ^= 0xff;
+        std::fs::write(&path, &log)?;
+
+        let reopened: Storage<Person, _, _> = Storage::open_wal(&path, BorshSerializer)?;
+        assert_eq!(reopened.load()?, make_person());
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn wal_failed_append_keeps_later_saves() -> TestResult {
+        let path = temp_path("wal_failed_append.log");
+        let mut wal = WalBackend::open(&path)?;
+        wal.write(&BorshSerializer.to_bytes(&make_person())?)?;
+        wal.fail_next_append(5);
+        let mut storage = Storage::with_backend(BorshSerializer, wal);
+
+        assert!(storage.save(&Person { name: "Bob".to_string(), age: 41 }).is_err());
+        assert_eq!(storage.load()?, make_person());
+        storage.save(&Person { name: "Carol".to_string(), age: 25 })?;
+        drop(storage);
+
+        let reopened: Storage<Person, _, _> = Storage::open_wal(&path, BorshSerializer)?;
+        assert_eq!(reopened.load()?, Person { name: "Carol".to_string(), age: 25 });
+        assert_eq!(reopened.backend().records(), 2);
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn wal_truncated_first_record_is_empty() -> TestResult {
+        let path = temp_path("wal_truncated_first_record.log");
+        let mut storage = Storage::open_wal(&path, BorshSerializer)?;
+        storage.save(&make_person())?;
+        drop(storage);
+
+        truncate_by(&path, 1)?;
+        let reopened: Storage<Person, _, _> = Storage::open_wal(&path, BorshSerializer)?;
+        assert!(!reopened.has_data());
+        assert!(reopened.load().unwrap_err().is_empty());
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn wal_compaction_keeps_latest_value() -> TestResult {
+        let path = temp_path("wal_compaction.log");
+        let mut storage =
+            Storage::with_backend(BorshSerializer, WalBackend::open(&path)?.compact_every(3));
+        for age in 0..6 {
+            storage.save(&Person { name: "Alice".to_string(), age })?;
+        }
+        // Compacted on the 3rd and 5th saves, then the 6th was appended.
+        assert_eq!(storage.backend().records(), 2);
+        let record_len = 8 + BorshSerializer.encoded_len(&make_person())? as u64;
+        assert_eq!(std::fs::metadata(&path)?.len(), 2 * record_len);
+        drop(storage);
+
+        let reopened: Storage<Person, _, _> = Storage::open_wal(&path, BorshSerializer)?;
+        assert_eq!(reopened.load()?.age, 5);
+        std::fs::remove_file(&path)?;
         Ok(())
     }
 }
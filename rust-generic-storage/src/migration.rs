@@ -0,0 +1,13 @@
+use crate::error::StorageError;
+
+pub type MigrateFn<S> = fn(&S, u16, &[u8]) -> Result<Vec<u8>, StorageError>;
+
+// A type stored with `Storage::with_migrations` records `SCHEMA_VERSION` in
+// the envelope header. When `load` finds an older version it calls
+// `migrate_from` once per step (`v` -> `v + 1`) until the bytes are current.
+// Each step receives and returns bytes in the serializer `S`'s format.
+pub trait Migrate<S> {
+    const SCHEMA_VERSION: u16;
+
+    fn migrate_from(serializer: &S, version: u16, bytes: &[u8]) -> Result<Vec<u8>, StorageError>;
+}
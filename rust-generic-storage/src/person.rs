@@ -2,8 +2,20 @@ use serde::{Serialize as SerdeSerialize, Deserialize as SerdeDeserialize};
 use borsh::{BorshSerialize, BorshDeserialize};
 use wincode::{SchemaWrite as  WincodeSerialize, SchemaRead as WincodeDeserialize};
 
+use crate::serializer::Viewable;
+
 #[derive(Debug, PartialEq, BorshSerialize, BorshDeserialize, SerdeSerialize, SerdeDeserialize, WincodeSerialize, WincodeDeserialize)]
 pub struct Person {
     pub name: String,
     pub age: u32,
+}
+
+#[derive(Debug, PartialEq, SerdeDeserialize, WincodeDeserialize)]
+pub struct PersonView<'a> {
+    pub name: &'a str,
+    pub age: u32,
+}
+
+impl Viewable for Person {
+    type View<'a> = PersonView<'a>;
 }
\ No newline at end of file
@@ -0,0 +1,53 @@
+use serde::{Serialize as SerdeSerialize, de::DeserializeOwned as SerdeDeserializeOwned};
+
+use crate::{
+    error::StorageError,
+    serializer::{
+        Serializer, borsh::BorshSerializer, serde::JsonSerializer, wincode::WincodeSerializer,
+    },
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatReport {
+    pub format: &'static str,
+    pub encoded_len: usize,
+    // `true` when encoding the decoded value reproduces the original bytes.
+    pub canonical: bool,
+}
+
+pub fn report_format<T, S: Serializer<T>>(format: &'static str, serializer: &S, value: &T) -> Result<FormatReport, StorageError> {
+    let bytes = serializer.to_bytes(value)?;
+    let roundtrip = serializer.to_bytes(&serializer.from_bytes(&bytes)?)?;
+
+    Ok(FormatReport {
+        format,
+        encoded_len: serializer.encoded_len(value)?,
+        canonical: bytes == roundtrip,
+    })
+}
+
+// Reports every serializer compiled into this build, in format id order.
+pub fn compare_formats<T>(value: &T) -> Result<Vec<FormatReport>, StorageError>
+where
+    T: SerdeSerialize + SerdeDeserializeOwned,
+    BorshSerializer: Serializer<T>,
+    WincodeSerializer: Serializer<T>,
+{
+    #[allow(unused_mut)]
+    let mut reports = vec![
+        report_format("borsh", &BorshSerializer, value)?,
+        report_format("json", &JsonSerializer, value)?,
+        report_format("wincode", &WincodeSerializer, value)?,
+    ];
+
+    #[cfg(feature = "bincode")]
+    reports.push(report_format("bincode", &crate::serializer::bincode::BincodeSerializer, value)?);
+    #[cfg(feature = "msgpack")]
+    reports.push(report_format("msgpack", &crate::serializer::msgpack::MsgPackSerializer, value)?);
+    #[cfg(feature = "cbor")]
+    reports.push(report_format("cbor", &crate::serializer::cbor::CborSerializer, value)?);
+    #[cfg(feature = "postcard")]
+    reports.push(report_format("postcard", &crate::serializer::postcard::PostcardSerializer, value)?);
+
+    Ok(reports)
+}
@@ -0,0 +1,56 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use sha2::{Digest, Sha256};
+
+use super::{Serializer, format_id};
+use crate::error::StorageError;
+
+pub const DISCRIMINATOR_LEN: usize = 8;
+
+// Anchor `#[account]` layout: an 8-byte discriminator followed by the Borsh
+// encoding of the account struct.
+pub struct AnchorAccountSerializer {
+    discriminator: [u8; DISCRIMINATOR_LEN],
+}
+
+impl AnchorAccountSerializer {
+    pub fn new(discriminator: [u8; DISCRIMINATOR_LEN]) -> Self {
+        Self { discriminator }
+    }
+
+    // Derives the discriminator the way Anchor does: the first 8 bytes of
+    // `sha256("account:<name>")`.
+    pub fn for_account(name: &str) -> Self {
+        let hash = Sha256::digest(format!("account:{name}"));
+        let mut discriminator = [0; DISCRIMINATOR_LEN];
+        discriminator.copy_from_slice(&hash[..DISCRIMINATOR_LEN]);
+        Self::new(discriminator)
+    }
+
+    pub fn discriminator(&self) -> [u8; DISCRIMINATOR_LEN] {
+        self.discriminator
+    }
+}
+
+impl<T: BorshSerialize + BorshDeserialize> Serializer<T> for AnchorAccountSerializer {
+    const FORMAT_ID: u8 = format_id::ANCHOR;
+
+    fn to_bytes(&self, value: &T) -> Result<Vec<u8>, StorageError> {
+        let mut bytes = self.discriminator.to_vec();
+        value
+            .serialize(&mut bytes)
+            .map_err(|err| StorageError::encode("anchor", err))?;
+        Ok(bytes)
+    }
+
+    // Accounts are often allocated larger than their encoding, so trailing
+    // bytes after the struct are ignored, as Anchor's own loader does.
+    fn from_bytes(&self, bytes: &[u8]) -> Result<T, StorageError> {
+        let Some((discriminator, mut data)) = bytes.split_first_chunk::<DISCRIMINATOR_LEN>() else {
+            return Err(StorageError::decode("anchor", "account data shorter than discriminator"));
+        };
+        if *discriminator != self.discriminator {
+            return Err(StorageError::decode_at("anchor", 0, "account discriminator mismatch"));
+        }
+        T::deserialize(&mut data).map_err(|err| StorageError::decode("anchor", err))
+    }
+}
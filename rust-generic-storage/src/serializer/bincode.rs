@@ -0,0 +1,20 @@
+use serde::{Serialize as SerdeSerialize, de::DeserializeOwned as SerdeDeserializeOwned};
+
+use super::{Serializer, format_id};
+use crate::error::StorageError;
+
+pub struct BincodeSerializer;
+
+impl<T: SerdeSerialize + SerdeDeserializeOwned> Serializer<T> for BincodeSerializer {
+    const FORMAT_ID: u8 = format_id::BINCODE;
+
+    fn to_bytes(&self, value: &T) -> Result<Vec<u8>, StorageError> {
+        bincode::serde::encode_to_vec(value, bincode::config::standard())
+            .map_err(|err| StorageError::encode("bincode", err))
+    }
+    fn from_bytes(&self, bytes: &[u8]) -> Result<T, StorageError> {
+        bincode::serde::decode_from_slice(bytes, bincode::config::standard())
+            .map(|(value, _)| value)
+            .map_err(|err| StorageError::decode("bincode", err))
+    }
+}
\ No newline at end of file
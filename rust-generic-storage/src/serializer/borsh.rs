@@ -1,14 +1,33 @@
+use std::io::{Read, Write};
+
 use borsh::{BorshSerialize, BorshDeserialize};
 
-use super::Serializer;
+use super::{Serializer, format_id};
+use crate::error::StorageError;
 
 pub struct BorshSerializer;
 
 impl<T: BorshSerialize + BorshDeserialize> Serializer<T> for BorshSerializer {
-    fn to_bytes(&self, value: &T) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
-        Ok(borsh::to_vec(value)?)
+    const FORMAT_ID: u8 = format_id::BORSH;
+
+    fn to_bytes(&self, value: &T) -> Result<Vec<u8>, StorageError> {
+        borsh::to_vec(value).map_err(|err| StorageError::encode("borsh", err))
+    }
+    fn from_bytes(&self, bytes: &[u8]) -> Result<T, StorageError> {
+        borsh::from_slice(bytes).map_err(|err| StorageError::decode("borsh", err))
+    }
+
+    fn encoded_len(&self, value: &T) -> Result<usize, StorageError> {
+        borsh::object_length(value).map_err(|err| StorageError::encode("borsh", err))
+    }
+
+    fn serialize_into<W: Write>(&self, value: &T, mut writer: W) -> Result<(), StorageError> {
+        value
+            .serialize(&mut writer)
+            .map_err(|err| StorageError::encode("borsh", err))
     }
-    fn from_bytes(&self, bytes: &[u8]) -> Result<T, Box<dyn std::error::Error>> {
-        Ok(borsh::from_slice(bytes)?)
+    // Reads exactly one value, so consecutive records can share a stream.
+    fn deserialize_from<R: Read>(&self, mut reader: R) -> Result<T, StorageError> {
+        T::deserialize_reader(&mut reader).map_err(|err| StorageError::decode("borsh", err))
     }
 }
\ No newline at end of file
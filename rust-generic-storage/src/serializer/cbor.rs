@@ -0,0 +1,19 @@
+use serde::{Serialize as SerdeSerialize, de::DeserializeOwned as SerdeDeserializeOwned};
+
+use super::{Serializer, format_id};
+use crate::error::StorageError;
+
+pub struct CborSerializer;
+
+impl<T: SerdeSerialize + SerdeDeserializeOwned> Serializer<T> for CborSerializer {
+    const FORMAT_ID: u8 = format_id::CBOR;
+
+    fn to_bytes(&self, value: &T) -> Result<Vec<u8>, StorageError> {
+        let mut bytes = Vec::new();
+        ciborium::into_writer(value, &mut bytes).map_err(|err| StorageError::encode("cbor", err))?;
+        Ok(bytes)
+    }
+    fn from_bytes(&self, bytes: &[u8]) -> Result<T, StorageError> {
+        ciborium::from_reader(bytes).map_err(|err| StorageError::decode("cbor", err))
+    }
+}
\ No newline at end of file
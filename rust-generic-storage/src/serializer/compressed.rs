@@ -0,0 +1,100 @@
+#[cfg(feature = "deflate")]
+use std::io::{Read, Write};
+
+use super::Serializer;
+use crate::error::StorageError;
+
+pub trait Compression {
+    fn compress(&self, bytes: &[u8]) -> Result<Vec<u8>, StorageError>;
+    fn decompress(&self, bytes: &[u8]) -> Result<Vec<u8>, StorageError>;
+}
+
+// Serializes with `inner` and compresses the result with `codec`. Keeps the
+// default `FORMAT_ID`, so enveloped blobs are never mistaken for plain
+// `inner` output by `Storage::load_auto`.
+pub struct Compressed<S, C> {
+    inner: S,
+    codec: C,
+}
+
+impl<S, C> Compressed<S, C> {
+    pub fn new(inner: S, codec: C) -> Self {
+        Self { inner, codec }
+    }
+}
+
+impl<T, S: Serializer<T>, C: Compression> Serializer<T> for Compressed<S, C> {
+    fn to_bytes(&self, value: &T) -> Result<Vec<u8>, StorageError> {
+        self.codec.compress(&self.inner.to_bytes(value)?)
+    }
+    fn from_bytes(&self, bytes: &[u8]) -> Result<T, StorageError> {
+        self.inner.from_bytes(&self.codec.decompress(bytes)?)
+    }
+}
+
+#[cfg(feature = "zstd")]
+pub struct Zstd {
+    pub level: i32,
+}
+
+#[cfg(feature = "zstd")]
+impl Default for Zstd {
+    fn default() -> Self {
+        Self { level: zstd::DEFAULT_COMPRESSION_LEVEL }
+    }
+}
+
+#[cfg(feature = "zstd")]
+impl Compression for Zstd {
+    fn compress(&self, bytes: &[u8]) -> Result<Vec<u8>, StorageError> {
+        zstd::encode_all(bytes, self.level).map_err(|err| StorageError::encode("zstd", err))
+    }
+    fn decompress(&self, bytes: &[u8]) -> Result<Vec<u8>, StorageError> {
+        zstd::decode_all(bytes).map_err(|err| StorageError::decode("zstd", err))
+    }
+}
+
+#[cfg(feature = "lz4")]
+#[derive(Default)]
+pub struct Lz4;
+
+#[cfg(feature = "lz4")]
+impl Compression for Lz4 {
+    fn compress(&self, bytes: &[u8]) -> Result<Vec<u8>, StorageError> {
+        Ok(lz4_flex::compress_prepend_size(bytes))
+    }
+    fn decompress(&self, bytes: &[u8]) -> Result<Vec<u8>, StorageError> {
+        lz4_flex::decompress_size_prepended(bytes).map_err(|err| StorageError::decode("lz4", err))
+    }
+}
+
+#[cfg(feature = "deflate")]
+pub struct Deflate {
+    pub level: u32,
+}
+
+#[cfg(feature = "deflate")]
+impl Default for Deflate {
+    fn default() -> Self {
+        Self { level: flate2::Compression::default().level() }
+    }
+}
+
+#[cfg(feature = "deflate")]
+impl Compression for Deflate {
+    fn compress(&self, bytes: &[u8]) -> Result<Vec<u8>, StorageError> {
+        let mut encoder =
+            flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::new(self.level));
+        encoder
+            .write_all(bytes)
+            .and_then(|_| encoder.finish())
+            .map_err(|err| StorageError::encode("deflate", err))
+    }
+    fn decompress(&self, bytes: &[u8]) -> Result<Vec<u8>, StorageError> {
+        let mut decompressed = Vec::new();
+        flate2::read::DeflateDecoder::new(bytes)
+            .read_to_end(&mut decompressed)
+            .map_err(|err| StorageError::decode("deflate", err))?;
+        Ok(decompressed)
+    }
+}
@@ -0,0 +1,57 @@
+use chacha20poly1305::{
+    ChaCha20Poly1305, Key, Nonce,
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+};
+
+use super::Serializer;
+use crate::error::StorageError;
+
+const NONCE_LEN: usize = 12;
+
+// Serializes with `inner` and seals the result with ChaCha20-Poly1305 under
+// a fresh random nonce: `nonce [12] | ciphertext | tag [16]`. Any modified
+// or truncated blob fails authentication and loads as `StorageError::Tampered`.
+pub struct Encrypted<S> {
+    inner: S,
+    cipher: ChaCha20Poly1305,
+}
+
+impl<S> Encrypted<S> {
+    pub fn new(inner: S, key: &[u8; 32]) -> Self {
+        Self {
+            inner,
+            cipher: ChaCha20Poly1305::new(Key::from_slice(key)),
+        }
+    }
+
+    pub fn generate_key() -> [u8; 32] {
+        ChaCha20Poly1305::generate_key(&mut OsRng).into()
+    }
+}
+
+impl<T, S: Serializer<T>> Serializer<T> for Encrypted<S> {
+    fn to_bytes(&self, value: &T) -> Result<Vec<u8>, StorageError> {
+        let plaintext = self.inner.to_bytes(value)?;
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, plaintext.as_slice())
+            .map_err(|_| StorageError::encode("chacha20poly1305", "encryption failed"))?;
+
+        let mut bytes = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+        bytes.extend_from_slice(&nonce);
+        bytes.extend_from_slice(&ciphertext);
+        Ok(bytes)
+    }
+    fn from_bytes(&self, bytes: &[u8]) -> Result<T, StorageError> {
+        if bytes.len() < NONCE_LEN {
+            return Err(StorageError::Tampered);
+        }
+        let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| StorageError::Tampered)?;
+        self.inner.from_bytes(&plaintext)
+    }
+}
\ No newline at end of file
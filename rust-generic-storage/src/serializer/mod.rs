@@ -1,8 +1,69 @@
 pub mod borsh;
+pub mod compressed;
 pub mod serde;
 pub mod wincode;
+#[cfg(feature = "bincode")]
+pub mod bincode;
+#[cfg(feature = "msgpack")]
+pub mod msgpack;
+#[cfg(feature = "cbor")]
+pub mod cbor;
+#[cfg(feature = "postcard")]
+pub mod postcard;
+#[cfg(feature = "encryption")]
+pub mod encrypted;
+#[cfg(feature = "anchor")]
+pub mod anchor;
+#[cfg(feature = "pod")]
+pub mod pod;
+
+use std::io::{Read, Write};
+
+use crate::error::StorageError;
+
+pub mod format_id {
+    pub const CUSTOM: u8 = 0;
+    pub const BORSH: u8 = 1;
+    pub const JSON: u8 = 2;
+    pub const WINCODE: u8 = 3;
+    pub const BINCODE: u8 = 4;
+    pub const MSGPACK: u8 = 5;
+    pub const CBOR: u8 = 6;
+    pub const POSTCARD: u8 = 7;
+    pub const ANCHOR: u8 = 8;
+    pub const POD: u8 = 9;
+}
 
 pub trait Serializer<T> {
-    fn to_bytes(&self, value: &T) -> Result<Vec<u8>, Box<dyn std::error::Error>>;
-    fn from_bytes(&self, bytes: &[u8]) -> Result<T, Box<dyn std::error::Error>>;
+    const FORMAT_ID: u8 = format_id::CUSTOM;
+
+    fn to_bytes(&self, value: &T) -> Result<Vec<u8>, StorageError>;
+    fn from_bytes(&self, bytes: &[u8]) -> Result<T, StorageError>;
+
+    fn encoded_len(&self, value: &T) -> Result<usize, StorageError> {
+        Ok(self.to_bytes(value)?.len())
+    }
+
+    // The defaults buffer through `to_bytes`/`from_bytes`; formats that can
+    // stream natively override them. `deserialize_from` reads `reader` to
+    // the end unless the format knows where a value stops.
+    fn serialize_into<W: Write>(&self, value: &T, mut writer: W) -> Result<(), StorageError> {
+        writer.write_all(&self.to_bytes(value)?)?;
+        Ok(())
+    }
+    fn deserialize_from<R: Read>(&self, mut reader: R) -> Result<T, StorageError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        self.from_bytes(&bytes)
+    }
+}
+
+pub trait BorrowingSerializer<'a, T> {
+    fn view_bytes(&self, bytes: &'a [u8]) -> Result<T, StorageError>;
+}
+
+// Ties an owned type to its borrowed counterpart (e.g. `Person` ->
+// `PersonView<'a>`) so `Storage::with_view` can name the view type.
+pub trait Viewable {
+    type View<'a>;
 }
\ No newline at end of file
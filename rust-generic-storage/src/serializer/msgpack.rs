@@ -0,0 +1,17 @@
+use serde::{Serialize as SerdeSerialize, de::DeserializeOwned as SerdeDeserializeOwned};
+
+use super::{Serializer, format_id};
+use crate::error::StorageError;
+
+pub struct MsgPackSerializer;
+
+impl<T: SerdeSerialize + SerdeDeserializeOwned> Serializer<T> for MsgPackSerializer {
+    const FORMAT_ID: u8 = format_id::MSGPACK;
+
+    fn to_bytes(&self, value: &T) -> Result<Vec<u8>, StorageError> {
+        rmp_serde::to_vec(value).map_err(|err| StorageError::encode("msgpack", err))
+    }
+    fn from_bytes(&self, bytes: &[u8]) -> Result<T, StorageError> {
+        rmp_serde::from_slice(bytes).map_err(|err| StorageError::decode("msgpack", err))
+    }
+}
\ No newline at end of file
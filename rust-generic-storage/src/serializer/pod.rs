@@ -0,0 +1,30 @@
+use bytemuck::Pod;
+
+use super::{BorrowingSerializer, Serializer, format_id};
+use crate::error::StorageError;
+
+// Raw `#[repr(C)]` layout, as used by Pinocchio programs. The stored bytes
+// are exactly `size_of::<T>()` long.
+pub struct PodSerializer;
+
+impl<T: Pod> Serializer<T> for PodSerializer {
+    const FORMAT_ID: u8 = format_id::POD;
+
+    fn to_bytes(&self, value: &T) -> Result<Vec<u8>, StorageError> {
+        Ok(bytemuck::bytes_of(value).to_vec())
+    }
+    fn from_bytes(&self, bytes: &[u8]) -> Result<T, StorageError> {
+        bytemuck::try_pod_read_unaligned(bytes).map_err(|err| StorageError::decode("pod", err))
+    }
+
+    fn encoded_len(&self, _value: &T) -> Result<usize, StorageError> {
+        Ok(size_of::<T>())
+    }
+}
+
+// Casts in place, so `bytes` must also be aligned for `T`.
+impl<'a, T: Pod> BorrowingSerializer<'a, &'a T> for PodSerializer {
+    fn view_bytes(&self, bytes: &'a [u8]) -> Result<&'a T, StorageError> {
+        bytemuck::try_from_bytes(bytes).map_err(|err| StorageError::decode("pod", err))
+    }
+}
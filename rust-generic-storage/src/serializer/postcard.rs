@@ -0,0 +1,17 @@
+use serde::{Serialize as SerdeSerialize, de::DeserializeOwned as SerdeDeserializeOwned};
+
+use super::{Serializer, format_id};
+use crate::error::StorageError;
+
+pub struct PostcardSerializer;
+
+impl<T: SerdeSerialize + SerdeDeserializeOwned> Serializer<T> for PostcardSerializer {
+    const FORMAT_ID: u8 = format_id::POSTCARD;
+
+    fn to_bytes(&self, value: &T) -> Result<Vec<u8>, StorageError> {
+        postcard::to_stdvec(value).map_err(|err| StorageError::encode("postcard", err))
+    }
+    fn from_bytes(&self, bytes: &[u8]) -> Result<T, StorageError> {
+        postcard::from_bytes(bytes).map_err(|err| StorageError::decode("postcard", err))
+    }
+}
\ No newline at end of file
@@ -1,14 +1,38 @@
-use serde::{Serialize as SerdeSerialize, de::DeserializeOwned as SerdeDeserializeOwned};
+use std::io::{Read, Write};
 
-use super::Serializer;
+use serde::{Deserialize as SerdeDeserialize, Serialize as SerdeSerialize, de::DeserializeOwned as SerdeDeserializeOwned};
+
+use super::{BorrowingSerializer, Serializer, format_id};
+use crate::error::StorageError;
 
 pub struct JsonSerializer;
 
 impl<T: SerdeSerialize + SerdeDeserializeOwned> Serializer<T> for JsonSerializer {
-    fn to_bytes(&self, value: &T) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
-        Ok(serde_json::to_vec(value)?)
+    const FORMAT_ID: u8 = format_id::JSON;
+
+    fn to_bytes(&self, value: &T) -> Result<Vec<u8>, StorageError> {
+        serde_json::to_vec(value).map_err(|err| StorageError::encode("json", err))
+    }
+    fn from_bytes(&self, bytes: &[u8]) -> Result<T, StorageError> {
+        Ok(serde_json::from_slice(bytes)?)
     }
-    fn from_bytes(&self, bytes: &[u8]) -> Result<T, Box<dyn std::error::Error>> {
+
+    fn serialize_into<W: Write>(&self, value: &T, writer: W) -> Result<(), StorageError> {
+        serde_json::to_writer(writer, value).map_err(|err| {
+            if err.is_io() {
+                StorageError::Io(err.into())
+            } else {
+                StorageError::encode("json", err)
+            }
+        })
+    }
+    fn deserialize_from<R: Read>(&self, reader: R) -> Result<T, StorageError> {
+        Ok(serde_json::from_reader(reader)?)
+    }
+}
+
+impl<'a, T: SerdeDeserialize<'a>> BorrowingSerializer<'a, T> for JsonSerializer {
+    fn view_bytes(&self, bytes: &'a [u8]) -> Result<T, StorageError> {
         Ok(serde_json::from_slice(bytes)?)
     }
 }
\ No newline at end of file
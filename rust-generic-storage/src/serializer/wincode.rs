@@ -1,14 +1,32 @@
-use wincode::{Serialize as WincodeSerialize, DeserializeOwned as WincodeDeserialize}; 
+use wincode::{
+    Serialize as WincodeSerialize, DeserializeOwned as WincodeDeserialize, SchemaRead as WincodeSchemaRead,
+    config::DefaultConfig,
+};
 
-use super::Serializer;
+use super::{BorrowingSerializer, Serializer, format_id};
+use crate::error::StorageError;
 
 pub struct WincodeSerializer;
 
 impl<T: WincodeSerialize<Src = T> + WincodeDeserialize<Dst = T>> Serializer<T> for WincodeSerializer {
-    fn to_bytes(&self, value: &T) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    const FORMAT_ID: u8 = format_id::WINCODE;
+
+    fn to_bytes(&self, value: &T) -> Result<Vec<u8>, StorageError> {
         Ok(wincode::serialize(value)?)
     }
-    fn from_bytes(&self, bytes: &[u8]) -> Result<T, Box<dyn std::error::Error>> {
+    fn from_bytes(&self, bytes: &[u8]) -> Result<T, StorageError> {
+        Ok(wincode::deserialize(bytes)?)
+    }
+
+    fn encoded_len(&self, value: &T) -> Result<usize, StorageError> {
+        Ok(wincode::serialized_size(value)? as usize)
+    }
+}
+
+impl<'a, T: WincodeSchemaRead<'a, DefaultConfig, Dst = T>> BorrowingSerializer<'a, T>
+    for WincodeSerializer
+{
+    fn view_bytes(&self, bytes: &'a [u8]) -> Result<T, StorageError> {
         Ok(wincode::deserialize(bytes)?)
     }
 }
\ No newline at end of file
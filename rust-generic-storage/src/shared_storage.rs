@@ -0,0 +1,100 @@
+use std::{
+    path::Path,
+    sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
+
+use crate::{
+    backend::{file::FileBackend, memory::MemoryBackend, StorageBackend},
+    error::StorageError,
+    serializer::Serializer,
+    storage::Storage,
+};
+
+// A `Storage` behind an `Arc<RwLock<_>>`. Clones share the same store, loads
+// run concurrently and saves are serialized.
+pub struct SharedStorage<T, S, B = MemoryBackend> {
+    inner: Arc<RwLock<Storage<T, S, B>>>,
+}
+
+impl<T, S, B> Clone for SharedStorage<T, S, B> {
+    fn clone(&self) -> Self {
+        Self { inner: Arc::clone(&self.inner) }
+    }
+}
+
+impl<T, S: Serializer<T>> SharedStorage<T, S> {
+    pub fn new(serializer: S) -> Self {
+        Self::from(Storage::new(serializer))
+    }
+}
+
+impl<T, S: Serializer<T>> SharedStorage<T, S, FileBackend> {
+    pub fn open(path: impl AsRef<Path>, serializer: S) -> Self {
+        Self::from(Storage::open(path, serializer))
+    }
+}
+
+impl<T, S, B> From<Storage<T, S, B>> for SharedStorage<T, S, B> {
+    fn from(storage: Storage<T, S, B>) -> Self {
+        Self { inner: Arc::new(RwLock::new(storage)) }
+    }
+}
+
+impl<T, S: Serializer<T>, B: StorageBackend> SharedStorage<T, S, B> {
+    pub fn save(&self, value: &T) -> Result<(), StorageError> {
+        self.write().save(value)
+    }
+
+    pub fn load(&self) -> Result<T, StorageError> {
+        self.read().load()
+    }
+
+    // Optimistic compare-and-swap: `f` runs without holding the lock, and
+    // its result is only saved if the stored bytes are still the ones it was
+    // computed from. Otherwise `f` is called again with the newer value.
+    // Returns the value that was saved.
+    pub fn update(&self, mut f: impl FnMut(T) -> T) -> Result<T, StorageError> {
+        loop {
+            let (snapshot, old) = {
+                let storage = self.read();
+                let bytes = storage.backend().read()?.ok_or(StorageError::Empty)?;
+                let old = storage.decode(&bytes)?;
+                (bytes, old)
+            };
+
+            let new = f(old);
+
+            let mut storage = self.write();
+            if storage.backend().read()?.as_deref() == Some(snapshot.as_slice()) {
+                storage.save(&new)?;
+                return Ok(new);
+            }
+        }
+    }
+
+    pub fn load_version(&self, n: usize) -> Result<T, StorageError> {
+        self.read().load_version(n)
+    }
+
+    pub fn rollback(&self) -> Result<(), StorageError> {
+        self.write().rollback()
+    }
+
+    pub fn history_len(&self) -> usize {
+        self.read().history_len()
+    }
+
+    pub fn has_data(&self) -> bool {
+        self.read().has_data()
+    }
+
+    // A panic while the lock was held cannot leave a half-written value
+    // behind (backends write whole blobs), so poisoning is ignored.
+    fn read(&self) -> RwLockReadGuard<'_, Storage<T, S, B>> {
+        self.inner.read().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, Storage<T, S, B>> {
+        self.inner.write().unwrap_or_else(|err| err.into_inner())
+    }
+}
@@ -1,45 +1,301 @@
-use crate::serializer::Serializer;
+use std::{collections::VecDeque, path::Path};
 
-pub struct Storage<T, S> {
-    data: Option<Vec<u8>>,
+use serde::{Serialize as SerdeSerialize, de::DeserializeOwned as SerdeDeserializeOwned};
+
+use crate::{
+    backend::{file::FileBackend, memory::MemoryBackend, wal::WalBackend, StorageBackend},
+    envelope,
+    error::StorageError,
+    migration::{Migrate, MigrateFn},
+    serializer::{
+        BorrowingSerializer, Serializer, Viewable, borsh::BorshSerializer, format_id,
+        serde::JsonSerializer, wincode::WincodeSerializer,
+    },
+};
+
+pub struct Storage<T, S, B = MemoryBackend> {
+    backend: B,
     serializer: S,
+    history: VecDeque<Vec<u8>>,
+    history_depth: usize,
+    envelope: Option<u16>,
+    migrate: Option<MigrateFn<S>>,
     _marker: std::marker::PhantomData<T>,
 }
 
 impl<T, S: Serializer<T>> Storage<T, S> {
     pub fn new(serializer: S) -> Self {
+        Self::with_backend(serializer, MemoryBackend::new())
+    }
+}
+
+impl<T, S: Serializer<T>> Storage<T, S, FileBackend> {
+    pub fn open(path: impl AsRef<Path>, serializer: S) -> Self {
+        Self::with_backend(serializer, FileBackend::new(path))
+    }
+}
+
+impl<T, S: Serializer<T>> Storage<T, S, WalBackend> {
+    // Replays the log at `path`, so the loaded value is the last save that
+    // fully reached the disk.
+    pub fn open_wal(path: impl AsRef<Path>, serializer: S) -> Result<Self, StorageError> {
+        Ok(Self::with_backend(serializer, WalBackend::open(path)?))
+    }
+}
+
+impl<T, S: Serializer<T>, B: StorageBackend> Storage<T, S, B> {
+    pub fn with_backend(serializer: S, backend: B) -> Self {
         Self {
-            data: None,
+            backend,
             serializer,
+            history: VecDeque::new(),
+            history_depth: 0,
+            envelope: None,
+            migrate: None,
             _marker: std::marker::PhantomData,
         }
     }
-    pub fn save(&mut self, value: &T) -> Result<(), Box<dyn std::error::Error>> {
-        self.data = Some(self.serializer.to_bytes(value)?);
-        Ok(())
+
+    // Keeps the last `depth` saved versions (the current one included) so
+    // they can be read back with `load_version` or restored with `rollback`.
+    // Only saves made through this `Storage` are recorded.
+    pub fn with_history(mut self, depth: usize) -> Self {
+        self.history_depth = depth;
+        self.history.truncate(depth);
+        self
+    }
+
+    // Wraps every saved value in an `envelope` header recording the
+    // serializer's format id and `schema_version`, and checks it on load:
+    // without `with_migrations`, any other schema version is an error.
+    pub fn with_envelope(mut self, schema_version: u16) -> Self {
+        self.envelope = Some(schema_version);
+        self
+    }
+
+    // Enables the envelope at `T::SCHEMA_VERSION` and upgrades older blobs
+    // through `T::migrate_from` on every load.
+    pub fn with_migrations(mut self) -> Self
+    where
+        T: Migrate<S>,
+    {
+        self.envelope = Some(T::SCHEMA_VERSION);
+        self.migrate = Some(T::migrate_from);
+        self
     }
 
-    pub fn load(&self) -> Result<T, Box<dyn std::error::Error>> {
-        if let Some(ref bytes) = self.data {
-            self.serializer.from_bytes(bytes)
-        } else {
-            Err("No data to load".into())
+    pub fn save(&mut self, value: &T) -> Result<(), StorageError> {
+        let bytes = self.encode(value)?;
+        self.backend.write(&bytes)?;
+        if self.history_depth > 0 {
+            self.history.push_front(bytes);
+            self.history.truncate(self.history_depth);
         }
+        Ok(())
+    }
+
+    pub fn load(&self) -> Result<T, StorageError> {
+        self.backend
+            .read_with(|bytes| self.decode(bytes))?
+            .ok_or(StorageError::Empty)?
+    }
+
+    // `0` is the most recent save, `1` the one before it, and so on.
+    pub fn load_version(&self, n: usize) -> Result<T, StorageError> {
+        let bytes = self
+            .history
+            .get(n)
+            .ok_or(StorageError::VersionNotFound { version: n })?;
+        self.decode(bytes)
+    }
+
+    pub fn rollback(&mut self) -> Result<(), StorageError> {
+        let previous = self
+            .history
+            .get(1)
+            .ok_or(StorageError::VersionNotFound { version: 1 })?;
+        self.backend.write(previous)?;
+        self.history.pop_front();
+        Ok(())
+    }
+
+    pub fn history_len(&self) -> usize {
+        self.history.len()
     }
 
     pub fn has_data(&self) -> bool {
-        self.data.is_some()
+        self.backend.exists()
+    }
+
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    pub fn into_backend(self) -> B {
+        self.backend
     }
 
-    pub fn convert<S2: Serializer<T>>(self, new_serializer: S2) -> Result<Storage<T, S2>, Box<dyn std::error::Error>> {
-        let bytes = self.data.ok_or("No data to convert")?;
-        let value = self.serializer.from_bytes(&bytes)?;
-        let new_bytes = new_serializer.to_bytes(&value)?;
+    // Migrations are tied to the old serializer, so they are not carried
+    // over; every stored version is rewritten at the current schema instead.
+    pub fn convert<S2: Serializer<T>>(self, new_serializer: S2) -> Result<Storage<T, S2, B>, StorageError> {
+        let bytes = self.backend.read()?.ok_or(StorageError::Empty)?;
+        let value = self.decode(&bytes)?;
+        let history = self
+            .history
+            .iter()
+            .map(|version| self.decode(version))
+            .collect::<Result<Vec<T>, StorageError>>()?;
 
-        Ok(Storage {
-            data: Some(new_bytes),
+        let mut converted = Storage {
+            backend: self.backend,
             serializer: new_serializer,
+            history: VecDeque::with_capacity(history.len()),
+            history_depth: self.history_depth,
+            envelope: self.envelope,
+            migrate: None,
             _marker: std::marker::PhantomData,
-        })
+        };
+
+        for version in &history {
+            let new_version = converted.encode(version)?;
+            converted.history.push_back(new_version);
+        }
+
+        let new_bytes = converted.encode(&value)?;
+        converted.backend.write(&new_bytes)?;
+        Ok(converted)
+    }
+
+    fn encode(&self, value: &T) -> Result<Vec<u8>, StorageError> {
+        encode(&self.serializer, self.envelope, value)
+    }
+
+    pub(crate) fn decode(&self, bytes: &[u8]) -> Result<T, StorageError> {
+        decode(&self.serializer, self.envelope, self.migrate, bytes)
+    }
+
+    fn unwrap_envelope<'b>(&self, bytes: &'b [u8]) -> Result<(Option<envelope::Header>, &'b [u8]), StorageError> {
+        unwrap_envelope::<T, S>(self.envelope, bytes)
     }
-}
\ No newline at end of file
+}
+
+// Shared with `AsyncStorage`, so both read and write the same blobs and run
+// the same migrations.
+pub(crate) fn encode<T, S: Serializer<T>>(
+    serializer: &S,
+    envelope: Option<u16>,
+    value: &T,
+) -> Result<Vec<u8>, StorageError> {
+    let payload = serializer.to_bytes(value)?;
+    match envelope {
+        Some(schema_version) => envelope::wrap(S::FORMAT_ID, schema_version, &payload),
+        None => Ok(payload),
+    }
+}
+
+pub(crate) fn decode<T, S: Serializer<T>>(
+    serializer: &S,
+    envelope: Option<u16>,
+    migrate: Option<MigrateFn<S>>,
+    bytes: &[u8],
+) -> Result<T, StorageError> {
+    let (header, payload) = unwrap_envelope::<T, S>(envelope, bytes)?;
+    let (Some(header), Some(schema_version)) = (header, envelope) else {
+        return serializer.from_bytes(payload);
+    };
+    if header.schema_version == schema_version {
+        return serializer.from_bytes(payload);
+    }
+
+    // Only older blobs can be migrated; anything else is a schema this
+    // `Storage` cannot read.
+    let migrate = match migrate {
+        Some(migrate) if header.schema_version < schema_version => migrate,
+        _ => {
+            return Err(StorageError::SchemaVersion {
+                found: header.schema_version,
+                current: schema_version,
+            });
+        }
+    };
+
+    let mut version = header.schema_version;
+    let mut migrated = payload.to_vec();
+    while version < schema_version {
+        migrated = migrate(serializer, version, &migrated)?;
+        version += 1;
+    }
+    serializer.from_bytes(&migrated)
+}
+
+fn unwrap_envelope<T, S: Serializer<T>>(
+    envelope: Option<u16>,
+    bytes: &[u8],
+) -> Result<(Option<envelope::Header>, &[u8]), StorageError> {
+    if envelope.is_none() {
+        return Ok((None, bytes));
+    }
+
+    let (header, payload) = envelope::unwrap(bytes)?;
+    if header.format_id != S::FORMAT_ID {
+        return Err(StorageError::FormatMismatch {
+            expected: S::FORMAT_ID,
+            found: header.format_id,
+        });
+    }
+    Ok((Some(header), payload))
+}
+
+impl<T: Viewable, S: Serializer<T>, B: StorageBackend> Storage<T, S, B> {
+    // Decodes a borrowed `T::View` straight out of the backend's buffer and
+    // hands it to `f`, skipping the allocations of a full `load`. Views are
+    // never migrated, so an outdated schema version is reported as an error.
+    pub fn with_view<R>(&self, f: impl for<'a> FnOnce(T::View<'a>) -> R) -> Result<R, StorageError>
+    where
+        S: for<'a> BorrowingSerializer<'a, T::View<'a>>,
+    {
+        self.backend
+            .read_with(|bytes| {
+                let (header, payload) = self.unwrap_envelope(bytes)?;
+                if let (Some(header), Some(schema_version)) = (header, self.envelope)
+                    && header.schema_version != schema_version
+                {
+                    return Err(StorageError::SchemaVersion {
+                        found: header.schema_version,
+                        current: schema_version,
+                    });
+                }
+                Ok(f(self.serializer.view_bytes(payload)?))
+            })?
+            .ok_or(StorageError::Empty)?
+    }
+}
+
+impl<T, S, B: StorageBackend> Storage<T, S, B>
+where
+    T: SerdeSerialize + SerdeDeserializeOwned,
+    S: Serializer<T>,
+    BorshSerializer: Serializer<T>,
+    WincodeSerializer: Serializer<T>,
+{
+    // Ignores `S` and picks the serializer named in the envelope header, so
+    // blobs written by any of the built-in formats can be read back.
+    pub fn load_auto(&self) -> Result<T, StorageError> {
+        let bytes = self.backend.read()?.ok_or(StorageError::Empty)?;
+        let (header, payload) = envelope::unwrap(&bytes)?;
+        match header.format_id {
+            format_id::BORSH => BorshSerializer.from_bytes(payload),
+            format_id::JSON => JsonSerializer.from_bytes(payload),
+            format_id::WINCODE => WincodeSerializer.from_bytes(payload),
+            #[cfg(feature = "bincode")]
+            format_id::BINCODE => crate::serializer::bincode::BincodeSerializer.from_bytes(payload),
+            #[cfg(feature = "msgpack")]
+            format_id::MSGPACK => crate::serializer::msgpack::MsgPackSerializer.from_bytes(payload),
+            #[cfg(feature = "cbor")]
+            format_id::CBOR => crate::serializer::cbor::CborSerializer.from_bytes(payload),
+            #[cfg(feature = "postcard")]
+            format_id::POSTCARD => crate::serializer::postcard::PostcardSerializer.from_bytes(payload),
+            format_id => Err(StorageError::UnknownFormat { format_id }),
+        }
+    }
+}
@@ -0,0 +1,114 @@
+// Generates a test module for `type` across every listed serializer:
+//
+// - `<serializer>::roundtrip` saves and loads each sample,
+// - `<serializer>::convert_matrix` converts each sample to every serializer,
+// - `<serializer>::proptest_roundtrip` (when `strategy` is given) roundtrips
+//   generated values; the calling crate needs `proptest` as a dependency,
+// - `encoded_len` checks each serializer's `encoded_len` against the bytes
+//   it actually writes for each sample.
+//
+// ```ignore
+// serializer_test_suite!(person_suite {
+//     type: Person,
+//     samples: [make_person(), Person { name: "".to_string(), age: 0 }],
+//     serializers: { borsh: BorshSerializer, json: JsonSerializer },
+//     strategy: person_strategy(),
+// });
+// ```
+#[macro_export]
+macro_rules! serializer_test_suite {
+    (
+        $suite:ident {
+            type: $ty:ty,
+            samples: $samples:tt,
+            serializers: $serializers:tt
+            $(, strategy: $strategy:expr)?
+            $(,)?
+        }
+    ) => {
+        mod $suite {
+            #[allow(unused_imports)]
+            use super::*;
+
+            fn samples() -> Vec<$ty> {
+                vec! $samples
+            }
+
+            $crate::serializer_test_suite!(@each $ty, $serializers, $serializers, [$($strategy)?]);
+
+            #[test]
+            fn encoded_len() {
+                $crate::serializer_test_suite!(@encoded_len $ty, $serializers);
+            }
+        }
+    };
+
+    (@each $ty:ty, { $($name:ident: $serializer:expr),+ $(,)? }, $all:tt, $strategy:tt) => {
+        $(
+            mod $name {
+                #[allow(unused_imports)]
+                use super::*;
+
+                #[test]
+                fn roundtrip() {
+                    for sample in samples() {
+                        let mut storage = $crate::storage::Storage::new($serializer);
+                        storage.save(&sample).unwrap();
+                        assert_eq!(storage.load().unwrap(), sample);
+                    }
+                }
+
+                #[test]
+                fn convert_matrix() {
+                    $crate::serializer_test_suite!(@convert $serializer, $all);
+                }
+
+                $crate::serializer_test_suite!(@proptest $serializer, $strategy);
+            }
+        )+
+    };
+
+    (@convert $source:expr, { $($name:ident: $target:expr),+ $(,)? }) => {
+        for sample in samples() {
+            $(
+                let mut storage = $crate::storage::Storage::new($source);
+                storage.save(&sample).unwrap();
+                let converted = storage.convert($target).unwrap();
+                assert_eq!(
+                    converted.load().unwrap(),
+                    sample,
+                    concat!("convert to ", stringify!($name))
+                );
+            )+
+        }
+    };
+
+    (@proptest $serializer:expr, []) => {};
+
+    (@proptest $serializer:expr, [$strategy:expr]) => {
+        ::proptest::proptest! {
+            #[test]
+            fn proptest_roundtrip(value in $strategy) {
+                let mut storage = $crate::storage::Storage::new($serializer);
+                storage.save(&value).unwrap();
+                ::proptest::prop_assert_eq!(storage.load().unwrap(), value);
+            }
+        }
+    };
+
+    (@encoded_len $ty:ty, { $($name:ident: $serializer:expr),+ $(,)? }) => {
+        for (index, sample) in samples().iter().enumerate() {
+            $(
+                let bytes = $crate::serializer::Serializer::<$ty>::to_bytes(&$serializer, sample).unwrap();
+                let encoded_len =
+                    $crate::serializer::Serializer::<$ty>::encoded_len(&$serializer, sample).unwrap();
+                assert_eq!(
+                    encoded_len,
+                    bytes.len(),
+                    "sample {index}: {} encoded_len",
+                    stringify!($name)
+                );
+            )+
+        }
+    };
+}